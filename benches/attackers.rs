@@ -0,0 +1,45 @@
+use std::time::Instant;
+
+use chess::board::{Color, Coord};
+use chess::position::Position;
+
+const POSITIONS: [(&str, &str); 3] = [
+    (
+        "start",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+/// Full-board scans per position, each asking for the attackers of all 64 squares
+/// from both sides
+const SCANS: u32 = 20_000;
+
+fn main() {
+    for (name, fen) in POSITIONS {
+        let position = Position::from_fen(fen).unwrap();
+
+        let start = Instant::now();
+        let mut attackers = 0;
+        for _ in 0..SCANS {
+            for row in 0..8 {
+                for col in 0..8 {
+                    for player in [Color::White, Color::Black] {
+                        attackers += position.get_attackers(Coord::make(row, col), player).len();
+                    }
+                }
+            }
+        }
+        let elapsed = start.elapsed();
+
+        let queries = SCANS * 64 * 2;
+        println!(
+            "{name:<10} {queries} queries in {elapsed:>10.2?} ({:.0} ns/query, {attackers} attackers)",
+            elapsed.as_nanos() as f64 / queries as f64
+        );
+    }
+}
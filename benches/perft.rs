@@ -0,0 +1,32 @@
+use std::time::Instant;
+
+use chess::position::Position;
+
+const POSITIONS: [(&str, &str, u32); 3] = [
+    (
+        "start",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        4,
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+];
+
+fn main() {
+    for (name, fen, depth) in POSITIONS {
+        let position = Position::from_fen(fen).unwrap();
+
+        let start = Instant::now();
+        let nodes = position.perft(depth);
+        let elapsed = start.elapsed();
+
+        println!(
+            "{name:<10} depth {depth}: {nodes:>8} nodes in {elapsed:>10.2?} ({:.0} nodes/s)",
+            nodes as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
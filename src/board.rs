@@ -1,11 +1,26 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Deref, DerefMut, Index, IndexMut};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     #[default]
     White,
     Black,
 }
 
+impl Color {
+    pub fn opponent(self) -> Self {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Piece {
     Pawn,
     Rook,
@@ -15,7 +30,20 @@ pub enum Piece {
     King,
 }
 
+impl Piece {
+    /// Every piece kind, in the order of their discriminants
+    pub const ALL: [Self; 6] = [
+        Self::Pawn,
+        Self::Rook,
+        Self::Knight,
+        Self::Bishop,
+        Self::Queen,
+        Self::King,
+    ];
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Square {
     #[default]
     Empty,
@@ -26,20 +54,272 @@ impl Square {
     pub fn is_empty(self) -> bool {
         matches!(self, Self::Empty)
     }
+
+    pub fn piece_kind(self) -> Option<Piece> {
+        match self {
+            Self::Piece(piece, _) => Some(piece),
+            Self::Empty => None,
+        }
+    }
+
+    pub fn player(self) -> Option<Color> {
+        match self {
+            Self::Piece(_, color) => Some(color),
+            Self::Empty => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coord {
     pub row: u8,
     pub col: u8,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+impl Coord {
+    pub const fn make(row: u8, col: u8) -> Self {
+        assert!(row < 8 && col < 8, "coordinate out of bounds");
+        Self { row, col }
+    }
+
+    pub const fn try_make(row: u8, col: u8) -> Option<Self> {
+        if row < 8 && col < 8 {
+            Some(Self { row, col })
+        } else {
+            None
+        }
+    }
+
+    /// Shade of the square, with a1 dark and h1 light
+    pub const fn square_color(self) -> Color {
+        if (self.row + self.col).is_multiple_of(2) {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+
+    /// Number of king moves between the two squares
+    pub const fn chebyshev_distance(self, other: Self) -> u8 {
+        let dy = self.row.abs_diff(other.row);
+        let dx = self.col.abs_diff(other.col);
+
+        if dy > dx {
+            dy
+        } else {
+            dx
+        }
+    }
+
+    /// Number of rook steps between the two squares, counting files and ranks separately
+    pub const fn manhattan_distance(self, other: Self) -> u8 {
+        self.row.abs_diff(other.row) + self.col.abs_diff(other.col)
+    }
+
+    /// The square one step away in `direction`, or `None` off the edge of the board
+    pub fn offset(self, direction: Direction) -> Option<Self> {
+        let (dx, dy) = direction.delta();
+        Self::try_make(
+            self.row.checked_add_signed(dy)?,
+            self.col.checked_add_signed(dx)?,
+        )
+    }
+
+    pub fn from_algebraic(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+
+        Some(Self {
+            row: rank as u8 - b'1',
+            col: file as u8 - b'a',
+        })
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.col) as char,
+            (b'1' + self.row) as char
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthNorthEast,
+    EastNorthEast,
+    EastSouthEast,
+    SouthSouthEast,
+    SouthSouthWest,
+    WestSouthWest,
+    WestNorthWest,
+    NorthNorthWest,
+}
+
+impl Direction {
+    /// The directions sliding pieces and kings move in
+    pub const COMPASS: [Self; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    pub const KNIGHT: [Self; 8] = [
+        Self::NorthNorthEast,
+        Self::EastNorthEast,
+        Self::EastSouthEast,
+        Self::SouthSouthEast,
+        Self::SouthSouthWest,
+        Self::WestSouthWest,
+        Self::WestNorthWest,
+        Self::NorthNorthWest,
+    ];
+
+    /// `(dx, dy)` step, with north towards rank 8 and east towards the h-file
+    pub const fn delta(self) -> (i8, i8) {
+        match self {
+            Self::North => (0, 1),
+            Self::NorthEast => (1, 1),
+            Self::East => (1, 0),
+            Self::SouthEast => (1, -1),
+            Self::South => (0, -1),
+            Self::SouthWest => (-1, -1),
+            Self::West => (-1, 0),
+            Self::NorthWest => (-1, 1),
+            Self::NorthNorthEast => (1, 2),
+            Self::EastNorthEast => (2, 1),
+            Self::EastSouthEast => (2, -1),
+            Self::SouthSouthEast => (1, -2),
+            Self::SouthSouthWest => (-1, -2),
+            Self::WestSouthWest => (-2, -1),
+            Self::WestNorthWest => (-2, 1),
+            Self::NorthNorthWest => (-1, 2),
+        }
+    }
+
+    pub const fn is_diagonal(self) -> bool {
+        matches!(
+            self,
+            Self::NorthEast | Self::SouthEast | Self::SouthWest | Self::NorthWest
+        )
+    }
+
+    /// The compass direction leading from `from` to `to`, if they share a line or diagonal
+    pub fn between(from: Coord, to: Coord) -> Option<Self> {
+        let (dx, dy) = Path { from, to }.distance();
+
+        if from == to || (dx != 0 && dy != 0 && dx.abs() != dy.abs()) {
+            return None;
+        }
+
+        Self::COMPASS
+            .into_iter()
+            .find(|direction| direction.delta() == (dx.signum(), dy.signum()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Path {
+    pub from: Coord,
+    pub to: Coord,
+}
+
+impl Path {
+    /// Signed `(dx, dy)` delta from `from` to `to`
+    pub fn distance(&self) -> (i8, i8) {
+        (
+            self.to.col as i8 - self.from.col as i8,
+            self.to.row as i8 - self.from.row as i8,
+        )
+    }
+}
+
+/// Mailbox for square lookups, kept in sync with per-piece and per-color bitboards
+/// where bit `row * 8 + col` is set for each occupied square
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "BoardRepr", into = "BoardRepr")
+)]
 pub struct Board {
-    pub squares: [[Square; 8]; 8],
+    squares: [[Square; 8]; 8],
+    pieces: [u64; 6],
+    colors: [u64; 2],
+    /// Square last handed out by `IndexMut`, which may have been written since. The
+    /// bitboards are patched for it on every read until the next `&mut` method folds
+    /// it back in
+    unsynced: Option<Coord>,
+}
+
+// Boards compare by their squares alone, as the bitboards follow from them
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.squares == other.squares
+    }
+}
+
+impl Eq for Board {}
+
+impl PartialOrd for Board {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Board {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.squares.cmp(&other.squares)
+    }
 }
 
 impl Board {
+    pub const fn from_squares(squares: [[Square; 8]; 8]) -> Self {
+        let mut board = Self {
+            squares,
+            pieces: [0; 6],
+            colors: [0; 2],
+            unsynced: None,
+        };
+
+        let mut i = 0;
+        while i < 64 {
+            if let Square::Piece(piece, color) = squares[i / 8][i % 8] {
+                board.pieces[piece as usize] |= 1 << i;
+                board.colors[color as usize] |= 1 << i;
+            }
+            i += 1;
+        }
+
+        board
+    }
+
+    pub const fn empty() -> Self {
+        Self::from_squares([[Square::Empty; 8]; 8])
+    }
+
     pub fn square(&self, coord: Coord) -> Option<Square> {
         self.squares
             .get(coord.row as usize)?
@@ -47,52 +327,657 @@ impl Board {
             .copied()
     }
 
-    pub fn square_mut(&mut self, coord: Coord) -> Option<&mut Square> {
-        self.squares
-            .get_mut(coord.row as usize)?
-            .get_mut(coord.col as usize)
+    /// Mutable access to the square at `coord`, with the bitboards updated to match
+    /// when the returned guard is dropped
+    pub fn square_mut(&mut self, coord: Coord) -> Option<SquareMut<'_>> {
+        let square = self.square(coord)?;
+
+        Some(SquareMut {
+            board: self,
+            coord,
+            square,
+        })
+    }
+
+    /// Replaces the contents of `coord`, returning what was there before
+    pub fn set_square(&mut self, coord: Coord, square: Square) -> Option<Square> {
+        self.sync();
+
+        let old = self.square(coord)?;
+        let bit = 1 << (coord.row * 8 + coord.col);
+
+        if let Square::Piece(piece, color) = old {
+            self.pieces[piece as usize] &= !bit;
+            self.colors[color as usize] &= !bit;
+        }
+        if let Square::Piece(piece, color) = square {
+            self.pieces[piece as usize] |= bit;
+            self.colors[color as usize] |= bit;
+        }
+
+        self.squares[coord.row as usize][coord.col as usize] = square;
+
+        Some(old)
+    }
+
+    /// Brings the bitboards up to date with a square written through `IndexMut`
+    fn sync(&mut self) {
+        if let Some(coord) = self.unsynced.take() {
+            let bit = 1 << (coord.row * 8 + coord.col);
+
+            for mask in self.pieces.iter_mut().chain(&mut self.colors) {
+                *mask &= !bit;
+            }
+            if let Square::Piece(piece, color) = self[coord] {
+                self.pieces[piece as usize] |= bit;
+                self.colors[color as usize] |= bit;
+            }
+        }
+    }
+
+    /// `mask` with the bit of a square written through `IndexMut` corrected to whether
+    /// that square now `matches`
+    fn patched(&self, mask: u64, matches: impl FnOnce(Square) -> bool) -> u64 {
+        match self.unsynced {
+            None => mask,
+            Some(coord) => {
+                let bit = 1 << (coord.row * 8 + coord.col);
+                if matches(self[coord]) {
+                    mask | bit
+                } else {
+                    mask & !bit
+                }
+            }
+        }
+    }
+
+    pub fn occupied(&self) -> u64 {
+        self.patched(self.colors[0] | self.colors[1], |square| !square.is_empty())
+    }
+
+    pub fn color_mask(&self, color: Color) -> u64 {
+        self.patched(self.colors[color as usize], |square| {
+            square.player() == Some(color)
+        })
+    }
+
+    pub fn piece_mask(&self, piece: Piece, color: Color) -> u64 {
+        self.patched(
+            self.pieces[piece as usize] & self.colors[color as usize],
+            |square| square == Square::Piece(piece, color),
+        )
+    }
+
+    pub fn count(&self, piece: Piece, color: Color) -> u8 {
+        self.piece_mask(piece, color).count_ones() as u8
+    }
+
+    /// Number of each piece on the board, indexed by color and then piece
+    pub fn census(&self) -> [[u8; 6]; 2] {
+        [Color::White, Color::Black].map(|color| Piece::ALL.map(|piece| self.count(piece, color)))
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Coord, Piece, Color)> + '_ {
+        mask_coords(self.occupied()).map(|coord| match self[coord] {
+            Square::Piece(piece, color) => (coord, piece, color),
+            Square::Empty => unreachable!("occupancy out of sync at {coord}"),
+        })
+    }
+
+    /// Walks from `from` (exclusive) in `direction` until leaving the board
+    pub fn ray(
+        &self,
+        from: Coord,
+        direction: Direction,
+    ) -> impl Iterator<Item = (Coord, Square)> + '_ {
+        core::iter::successors(from.offset(direction), move |coord| coord.offset(direction))
+            .map(|coord| (coord, self[coord]))
+    }
+
+    /// Walks from `from` (exclusive) by the `(dx, dy)` increment until leaving the board.
+    /// `ray` covers the compass directions, this any other step
+    pub fn path_iter(
+        &self,
+        from: Coord,
+        increment: (i8, i8),
+    ) -> impl Iterator<Item = (Coord, Square)> + '_ {
+        let (dx, dy) = increment;
+        core::iter::successors(Some(from), move |coord| {
+            let row = coord.row.checked_add_signed(dy)?;
+            let col = coord.col.checked_add_signed(dx)?;
+            Coord::try_make(row, col)
+        })
+        .skip(1)
+        .map(|coord| (coord, self[coord]))
     }
 
     pub fn move_piece(&mut self, from: Coord, to: Coord) -> Option<Square> {
-        let from_square = self.square_mut(from)?;
-        let from_copy = *from_square;
-        *from_square = Square::Empty;
-
-        let to_square = self.square_mut(to)?;
-        let to_copy = *to_square;
-        *to_square = from_copy;
-
-        Some(to_copy)
-    }
-}
-
-pub const STANDARD_BOARD: Board = Board {
-    squares: [
-        [
-            Square::Piece(Piece::Rook, Color::White),
-            Square::Piece(Piece::Knight, Color::White),
-            Square::Piece(Piece::Bishop, Color::White),
-            Square::Piece(Piece::Queen, Color::White),
-            Square::Piece(Piece::King, Color::White),
-            Square::Piece(Piece::Bishop, Color::White),
-            Square::Piece(Piece::Knight, Color::White),
-            Square::Piece(Piece::Rook, Color::White),
-        ],
-        [Square::Piece(Piece::Pawn, Color::White); 8],
-        [Square::Empty; 8],
-        [Square::Empty; 8],
-        [Square::Empty; 8],
-        [Square::Empty; 8],
-        [Square::Piece(Piece::Pawn, Color::Black); 8],
-        [
-            Square::Piece(Piece::Rook, Color::Black),
-            Square::Piece(Piece::Knight, Color::Black),
-            Square::Piece(Piece::Bishop, Color::Black),
-            Square::Piece(Piece::Queen, Color::Black),
-            Square::Piece(Piece::King, Color::Black),
-            Square::Piece(Piece::Bishop, Color::Black),
-            Square::Piece(Piece::Knight, Color::Black),
-            Square::Piece(Piece::Rook, Color::Black),
-        ],
+        let from_square = self.set_square(from, Square::Empty)?;
+        self.set_square(to, from_square)
+    }
+
+    /// One byte per square in row-major order: 0 for empty, otherwise the piece
+    /// kind plus one in the low three bits and the color in bit 3
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+
+        for (coord, piece, color) in self.pieces() {
+            bytes[(coord.row * 8 + coord.col) as usize] = (piece as u8 + 1) | (color as u8) << 3;
+        }
+
+        bytes
+    }
+
+    /// Inverse of `to_bytes`, rejecting any byte that doesn't name a square
+    pub fn from_bytes(bytes: [u8; 64]) -> Option<Self> {
+        let mut board = Self::empty();
+
+        for (i, byte) in bytes.into_iter().enumerate() {
+            if byte == 0 {
+                continue;
+            }
+
+            let piece = match byte & 0b0111 {
+                1 => Piece::Pawn,
+                2 => Piece::Rook,
+                3 => Piece::Knight,
+                4 => Piece::Bishop,
+                5 => Piece::Queen,
+                6 => Piece::King,
+                _ => return None,
+            };
+            let color = match byte >> 3 {
+                0 => Color::White,
+                1 => Color::Black,
+                _ => return None,
+            };
+
+            board.set_square(
+                Coord::make(i as u8 / 8, i as u8 % 8),
+                Square::Piece(piece, color),
+            );
+        }
+
+        Some(board)
+    }
+}
+
+impl Index<Coord> for Board {
+    type Output = Square;
+
+    fn index(&self, coord: Coord) -> &Square {
+        &self.squares[coord.row as usize][coord.col as usize]
+    }
+}
+
+/// Writes such as `board[coord] = square` are picked up by the bitboards lazily, so
+/// `set_square` is still the cheaper way to change a square
+impl IndexMut<Coord> for Board {
+    fn index_mut(&mut self, coord: Coord) -> &mut Square {
+        self.sync();
+        // Indexed first, so a coord off the board panics before it's recorded
+        let _ = self[coord];
+        self.unsynced = Some(coord);
+
+        &mut self.squares[coord.row as usize][coord.col as usize]
+    }
+}
+
+/// A square borrowed through `Board::square_mut`, written back on drop so the
+/// bitboards never disagree with the mailbox
+#[derive(Debug)]
+pub struct SquareMut<'a> {
+    board: &'a mut Board,
+    coord: Coord,
+    square: Square,
+}
+
+impl Deref for SquareMut<'_> {
+    type Target = Square;
+
+    fn deref(&self) -> &Square {
+        &self.square
+    }
+}
+
+impl DerefMut for SquareMut<'_> {
+    fn deref_mut(&mut self) -> &mut Square {
+        &mut self.square
+    }
+}
+
+impl Drop for SquareMut<'_> {
+    fn drop(&mut self) {
+        self.board.set_square(self.coord, self.square);
+    }
+}
+
+/// Serialized form of `Board`, holding only the mailbox so the bitboards are always
+/// rebuilt from it rather than trusted from the input
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Board")]
+struct BoardRepr {
+    squares: [[Square; 8]; 8],
+}
+
+#[cfg(feature = "serde")]
+impl From<BoardRepr> for Board {
+    fn from(repr: BoardRepr) -> Self {
+        Self::from_squares(repr.squares)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> Self {
+        Self {
+            squares: board.squares,
+        }
+    }
+}
+
+/// Coordinates of the set bits in `mask`, in row-major order
+pub fn mask_coords(mut mask: u64) -> impl Iterator<Item = Coord> {
+    core::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+
+        let i = mask.trailing_zeros() as u8;
+        mask &= mask - 1;
+
+        Some(Coord {
+            row: i / 8,
+            col: i % 8,
+        })
+    })
+}
+
+pub const STANDARD_BOARD: Board = Board::from_squares([
+    [
+        Square::Piece(Piece::Rook, Color::White),
+        Square::Piece(Piece::Knight, Color::White),
+        Square::Piece(Piece::Bishop, Color::White),
+        Square::Piece(Piece::Queen, Color::White),
+        Square::Piece(Piece::King, Color::White),
+        Square::Piece(Piece::Bishop, Color::White),
+        Square::Piece(Piece::Knight, Color::White),
+        Square::Piece(Piece::Rook, Color::White),
+    ],
+    [Square::Piece(Piece::Pawn, Color::White); 8],
+    [Square::Empty; 8],
+    [Square::Empty; 8],
+    [Square::Empty; 8],
+    [Square::Empty; 8],
+    [Square::Piece(Piece::Pawn, Color::Black); 8],
+    [
+        Square::Piece(Piece::Rook, Color::Black),
+        Square::Piece(Piece::Knight, Color::Black),
+        Square::Piece(Piece::Bishop, Color::Black),
+        Square::Piece(Piece::Queen, Color::Black),
+        Square::Piece(Piece::King, Color::Black),
+        Square::Piece(Piece::Bishop, Color::Black),
+        Square::Piece(Piece::Knight, Color::Black),
+        Square::Piece(Piece::Rook, Color::Black),
     ],
-};
+]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_algebraic_parses_squares() {
+        assert_eq!(Coord::from_algebraic("a1"), Some(Coord::make(0, 0)));
+        assert_eq!(Coord::from_algebraic("e4"), Some(Coord::make(3, 4)));
+        assert_eq!(Coord::from_algebraic("h8"), Some(Coord::make(7, 7)));
+    }
+
+    #[test]
+    fn from_algebraic_rejects_invalid_squares() {
+        for s in ["i9", "e", "e44", "E4", "e0", "e9", "", "4e"] {
+            assert_eq!(Coord::from_algebraic(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn algebraic_round_trips() {
+        for row in 0..8 {
+            for col in 0..8 {
+                let coord = Coord::make(row, col);
+                assert_eq!(Coord::from_algebraic(&coord.to_string()), Some(coord));
+            }
+        }
+        assert_eq!(Coord::make(3, 4).to_string(), "e4");
+    }
+
+    #[test]
+    fn try_make_accepts_the_board_edges() {
+        assert_eq!(Coord::try_make(0, 0), Some(Coord { row: 0, col: 0 }));
+        assert_eq!(Coord::try_make(7, 7), Some(Coord { row: 7, col: 7 }));
+        assert_eq!(Coord::try_make(0, 7), Some(Coord { row: 0, col: 7 }));
+    }
+
+    #[test]
+    fn try_make_rejects_out_of_range() {
+        assert_eq!(Coord::try_make(8, 0), None);
+        assert_eq!(Coord::try_make(0, 8), None);
+        assert_eq!(Coord::try_make(u8::MAX, u8::MAX), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "coordinate out of bounds")]
+    fn make_panics_out_of_range() {
+        Coord::make(8, 0);
+    }
+
+    #[test]
+    fn square_accessors() {
+        let knight = Square::Piece(Piece::Knight, Color::Black);
+        assert_eq!(knight.piece_kind(), Some(Piece::Knight));
+        assert_eq!(knight.player(), Some(Color::Black));
+
+        assert_eq!(Square::Empty.piece_kind(), None);
+        assert_eq!(Square::Empty.player(), None);
+    }
+
+    #[test]
+    fn path_distance_is_signed() {
+        let path = Path {
+            from: Coord::make(1, 6),
+            to: Coord::make(4, 2),
+        };
+
+        assert_eq!(path.distance(), (-4, 3));
+    }
+
+    #[test]
+    fn opponent_swaps_colors() {
+        assert_eq!(Color::White.opponent(), Color::Black);
+        assert_eq!(Color::Black.opponent(), Color::White);
+    }
+
+    #[test]
+    fn pieces_lists_every_occupied_square() {
+        assert_eq!(STANDARD_BOARD.pieces().count(), 32);
+        assert_eq!(
+            STANDARD_BOARD
+                .pieces()
+                .filter(|&(_, _, color)| color == Color::White)
+                .count(),
+            16
+        );
+        assert!(STANDARD_BOARD
+            .pieces()
+            .all(|(coord, piece, color)| STANDARD_BOARD[coord] == Square::Piece(piece, color)));
+    }
+
+    #[test]
+    fn index_reads_what_was_written() {
+        let mut board = Board::empty();
+        let e4 = Coord::make(3, 4);
+        let queen = Square::Piece(Piece::Queen, Color::White);
+
+        assert_eq!(board.set_square(e4, queen), Some(Square::Empty));
+        assert_eq!(board[e4], queen);
+        assert_eq!(board.square(e4), Some(queen));
+        assert_eq!(board.piece_mask(Piece::Queen, Color::White), 1 << 28);
+
+        assert_eq!(board.square(Coord { row: 8, col: 0 }), None);
+    }
+
+    #[test]
+    fn index_writes_keep_the_bitboards_in_sync() {
+        let mut board = Board::empty();
+        let e4 = Coord::make(3, 4);
+        let d5 = Coord::make(4, 3);
+
+        board[e4] = Square::Piece(Piece::Queen, Color::White);
+        assert_eq!(board.piece_mask(Piece::Queen, Color::White), 1 << 28);
+        assert_eq!(board.color_mask(Color::White), 1 << 28);
+        assert_eq!(board.occupied(), 1 << 28);
+
+        // Writing the same square again replaces the piece
+        board[e4] = Square::Piece(Piece::Knight, Color::Black);
+        assert_eq!(board.piece_mask(Piece::Queen, Color::White), 0);
+        assert_eq!(board.piece_mask(Piece::Knight, Color::Black), 1 << 28);
+
+        // Moving on to another square folds the earlier write in
+        board[d5] = Square::Piece(Piece::Pawn, Color::White);
+        board.set_square(Coord::make(0, 0), Square::Piece(Piece::King, Color::White));
+        assert_eq!(board.piece_mask(Piece::Knight, Color::Black), 1 << 28);
+        assert_eq!(board.piece_mask(Piece::Pawn, Color::White), 1 << 35);
+        assert_eq!(board.color_mask(Color::White), 1 << 35 | 1);
+
+        board[e4] = Square::Empty;
+        assert_eq!(board.color_mask(Color::Black), 0);
+        assert_eq!(board.census()[Color::Black as usize], [0; 6]);
+        assert_eq!(board.pieces().count(), 2);
+
+        let mut expected = Board::empty();
+        expected.set_square(d5, Square::Piece(Piece::Pawn, Color::White));
+        expected.set_square(Coord::make(0, 0), Square::Piece(Piece::King, Color::White));
+        assert_eq!(board, expected);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut every_piece = Board::empty();
+        for (i, piece) in Piece::ALL.into_iter().enumerate() {
+            every_piece.set_square(Coord::make(2, i as u8), Square::Piece(piece, Color::White));
+            every_piece.set_square(Coord::make(5, i as u8), Square::Piece(piece, Color::Black));
+        }
+
+        for board in [Board::empty(), STANDARD_BOARD, every_piece] {
+            assert_eq!(Board::from_bytes(board.to_bytes()), Some(board));
+        }
+    }
+
+    #[test]
+    fn from_bytes_rejects_unknown_codes() {
+        for code in [7, 8, 15, 16, 0xff] {
+            let mut bytes = [0; 64];
+            bytes[10] = code;
+            assert_eq!(Board::from_bytes(bytes), None, "{code}");
+        }
+    }
+
+    #[test]
+    fn square_colors() {
+        assert_eq!(Coord::make(0, 0).square_color(), Color::Black);
+        assert_eq!(Coord::make(0, 7).square_color(), Color::White);
+        assert!((0..8).all(|i| Coord::make(i, i).square_color() == Color::Black));
+    }
+
+    #[test]
+    fn offset_steps_within_the_board() {
+        let e4 = Coord::make(3, 4);
+        assert_eq!(e4.offset(Direction::North), Some(Coord::make(4, 4)));
+        assert_eq!(e4.offset(Direction::SouthWest), Some(Coord::make(2, 3)));
+        assert_eq!(e4.offset(Direction::EastNorthEast), Some(Coord::make(4, 6)));
+    }
+
+    #[test]
+    fn offset_off_an_edge_is_none() {
+        let a1 = Coord::make(0, 0);
+        let h8 = Coord::make(7, 7);
+
+        for direction in [Direction::South, Direction::West, Direction::SouthEast] {
+            assert_eq!(a1.offset(direction), None, "{direction:?}");
+        }
+        for direction in [Direction::North, Direction::East, Direction::NorthWest] {
+            assert_eq!(h8.offset(direction), None, "{direction:?}");
+        }
+
+        // Knight jumps can leave the board from a square away from the edge
+        assert_eq!(Coord::make(1, 1).offset(Direction::WestSouthWest), None);
+        assert_eq!(Coord::make(6, 6).offset(Direction::NorthNorthEast), None);
+    }
+
+    #[test]
+    fn ray_from_a1_north_east_stops_at_h8() {
+        let coords: Vec<Coord> = STANDARD_BOARD
+            .ray(Coord::make(0, 0), Direction::NorthEast)
+            .map(|(coord, _)| coord)
+            .collect();
+
+        assert_eq!(
+            coords,
+            (1..8).map(|i| Coord::make(i, i)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            STANDARD_BOARD
+                .ray(Coord::make(7, 7), Direction::NorthEast)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn ray_reports_squares_as_it_goes() {
+        let squares: Vec<Square> = STANDARD_BOARD
+            .ray(Coord::make(0, 0), Direction::NorthEast)
+            .map(|(_, square)| square)
+            .collect();
+
+        assert_eq!(squares[0], Square::Piece(Piece::Pawn, Color::White));
+        assert!(squares[1..5].iter().all(|square| square.is_empty()));
+        assert_eq!(squares[5], Square::Piece(Piece::Pawn, Color::Black));
+        assert_eq!(squares[6], Square::Piece(Piece::Rook, Color::Black));
+    }
+
+    #[test]
+    fn path_iter_matches_ray_along_compass_directions() {
+        let from = Coord::make(3, 2);
+
+        for direction in Direction::COMPASS {
+            assert!(
+                STANDARD_BOARD
+                    .path_iter(from, direction.delta())
+                    .eq(STANDARD_BOARD.ray(from, direction)),
+                "{direction:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn path_iter_takes_any_step() {
+        let coords: Vec<Coord> = Board::empty()
+            .path_iter(Coord::make(0, 0), (2, 1))
+            .map(|(coord, _)| coord)
+            .collect();
+
+        assert_eq!(
+            coords,
+            [Coord::make(1, 2), Coord::make(2, 4), Coord::make(3, 6)]
+        );
+    }
+
+    #[test]
+    fn square_mut_keeps_the_bitboards_in_sync() {
+        let mut board = STANDARD_BOARD;
+        let e2 = Coord::make(1, 4);
+        let e4 = Coord::make(3, 4);
+
+        *board.square_mut(e2).unwrap() = Square::Empty;
+        *board.square_mut(e4).unwrap() = Square::Piece(Piece::Pawn, Color::White);
+
+        assert_eq!(board[e2], Square::Empty);
+        assert_eq!(board[e4], Square::Piece(Piece::Pawn, Color::White));
+        assert_eq!(
+            board.piece_mask(Piece::Pawn, Color::White),
+            0xff00 & !(1 << 12) | 1 << 28
+        );
+        assert_eq!(Board::from_bytes(board.to_bytes()), Some(board));
+
+        assert!(board.square_mut(Coord { row: 0, col: 8 }).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_only_stores_the_squares() {
+        let json = serde_json::to_string(&STANDARD_BOARD).unwrap();
+        assert!(json.starts_with("{\"squares\":"));
+        assert!(!json.contains("colors"));
+
+        let board: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(board, STANDARD_BOARD);
+        assert_eq!(board.count(Piece::Knight, Color::Black), 2);
+    }
+
+    #[test]
+    fn empty_board_has_no_pieces() {
+        let board = Board::empty();
+
+        assert_eq!(board.pieces().count(), 0);
+        assert_eq!(board.occupied(), 0);
+        assert_eq!(board, Board::default());
+    }
+
+    #[test]
+    fn start_position_census() {
+        for color in [Color::White, Color::Black] {
+            assert_eq!(STANDARD_BOARD.count(Piece::Pawn, color), 8);
+            assert_eq!(STANDARD_BOARD.count(Piece::Rook, color), 2);
+            assert_eq!(STANDARD_BOARD.count(Piece::King, color), 1);
+        }
+        assert_eq!(STANDARD_BOARD.census(), [[8, 2, 2, 2, 1, 1]; 2]);
+    }
+
+    #[test]
+    fn census_of_a_custom_board() {
+        let mut board = Board::empty();
+        board.set_square(Coord::make(0, 4), Square::Piece(Piece::King, Color::White));
+        board.set_square(Coord::make(1, 0), Square::Piece(Piece::Pawn, Color::White));
+        board.set_square(Coord::make(1, 1), Square::Piece(Piece::Pawn, Color::White));
+        board.set_square(Coord::make(7, 4), Square::Piece(Piece::King, Color::Black));
+        board.set_square(Coord::make(7, 3), Square::Piece(Piece::Queen, Color::Black));
+        board.set_square(
+            Coord::make(5, 5),
+            Square::Piece(Piece::Knight, Color::Black),
+        );
+
+        assert_eq!(board.census(), [[2, 0, 0, 0, 0, 1], [0, 0, 1, 0, 1, 1]]);
+        assert_eq!(board.count(Piece::Queen, Color::White), 0);
+        assert_eq!(board.count(Piece::Queen, Color::Black), 1);
+    }
+
+    #[test]
+    fn corner_to_corner_distances() {
+        let a1 = Coord::make(0, 0);
+        let h8 = Coord::make(7, 7);
+
+        assert_eq!(a1.chebyshev_distance(h8), 7);
+        assert_eq!(a1.manhattan_distance(h8), 14);
+        assert_eq!(h8.chebyshev_distance(a1), 7);
+        assert_eq!(a1.chebyshev_distance(a1), 0);
+    }
+
+    #[test]
+    fn neighbours_are_one_king_move_apart() {
+        let e4 = Coord::make(3, 4);
+
+        for direction in Direction::COMPASS {
+            let neighbour = e4.offset(direction).unwrap();
+            assert_eq!(e4.chebyshev_distance(neighbour), 1, "{neighbour}");
+            assert!((1..=2).contains(&e4.manhattan_distance(neighbour)));
+        }
+    }
+
+    #[test]
+    fn squares_report_owners_as_colors() {
+        for coord in (0..8).flat_map(|row| (0..8).map(move |col| Coord::make(row, col))) {
+            let square = STANDARD_BOARD[coord];
+            let expected = match coord.row {
+                0 | 1 => Some(Color::White),
+                6 | 7 => Some(Color::Black),
+                _ => None,
+            };
+
+            assert_eq!(square.player(), expected, "{coord}");
+            assert_eq!(square.is_empty(), expected.is_none(), "{coord}");
+            assert_eq!(square.piece_kind().is_some(), !square.is_empty(), "{coord}");
+        }
+    }
+}
@@ -0,0 +1,253 @@
+mod keys;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::board::{Color, Coord, Direction, Piece, Square};
+use crate::position::{MoveInfo, Position};
+
+use keys::RANDOM64;
+
+const CASTLE_KEYS: usize = 768;
+const EN_PASSANT_KEYS: usize = CASTLE_KEYS + 4;
+const TURN_KEY: usize = EN_PASSANT_KEYS + 8;
+
+/// Each book entry is a big-endian key, move, weight and learn value
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookError {
+    Io(std::io::ErrorKind),
+    /// The data doesn't divide into whole 16-byte entries
+    Truncated,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(kind) => write!(f, "could not read book: {kind}"),
+            Self::Truncated => f.write_str("book ends partway through an entry"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// Opening book in the Polyglot `.bin` format
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PolyglotBook {
+    /// Sorted by key, as the format requires
+    entries: Vec<Entry>,
+}
+
+impl PolyglotBook {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        let bytes = fs::read(path).map_err(|err| BookError::Io(err.kind()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::Truncated);
+        }
+
+        let mut entries: Vec<Entry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| Entry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+            })
+            .collect();
+
+        // Lookups rely on the order, so don't trust the file to be sorted
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Self { entries })
+    }
+
+    /// Book moves for `position` with their weights, heaviest first
+    pub fn moves(&self, position: &Position) -> Vec<(MoveInfo, u16)> {
+        let key = position.polyglot_key();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        let mut moves: Vec<(MoveInfo, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| Some((decode_move(position, entry.raw_move)?, entry.weight)))
+            .collect();
+
+        moves.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
+
+        moves
+    }
+}
+
+impl Position {
+    /// Zobrist key as defined by the Polyglot book format
+    pub fn polyglot_key(&self) -> u64 {
+        let mut key = 0;
+
+        for (coord, piece, color) in self.board().pieces() {
+            let kind = match piece {
+                Piece::Pawn => 0,
+                Piece::Knight => 1,
+                Piece::Bishop => 2,
+                Piece::Rook => 3,
+                Piece::Queen => 4,
+                Piece::King => 5,
+            } * 2
+                + (color == Color::White) as usize;
+
+            key ^= RANDOM64[64 * kind + 8 * coord.row as usize + coord.col as usize];
+        }
+
+        for (i, player) in [Color::White, Color::Black].into_iter().enumerate() {
+            let rights = self.castle_rights(player);
+            if rights.king {
+                key ^= RANDOM64[CASTLE_KEYS + 2 * i];
+            }
+            if rights.queen {
+                key ^= RANDOM64[CASTLE_KEYS + 2 * i + 1];
+            }
+        }
+
+        // The en passant file only counts when a pawn is actually in place to capture
+        if let Some(target) = self.en_passant() {
+            let (back, pawn) = match self.to_play() {
+                Color::White => (Direction::South, Square::Piece(Piece::Pawn, Color::White)),
+                Color::Black => (Direction::North, Square::Piece(Piece::Pawn, Color::Black)),
+            };
+
+            let can_capture = target
+                .offset(back)
+                .into_iter()
+                .flat_map(|pushed| {
+                    [
+                        pushed.offset(Direction::West),
+                        pushed.offset(Direction::East),
+                    ]
+                })
+                .flatten()
+                .any(|coord| self.board()[coord] == pawn);
+
+            if can_capture {
+                key ^= RANDOM64[EN_PASSANT_KEYS + target.col as usize];
+            }
+        }
+
+        if self.to_play() == Color::White {
+            key ^= RANDOM64[TURN_KEY];
+        }
+
+        key
+    }
+}
+
+/// Polyglot packs the move as to file, to rank, from file, from rank and promotion, three
+/// bits each, with castling written as the king taking its own rook
+fn decode_move(position: &Position, raw_move: u16) -> Option<MoveInfo> {
+    let field = |shift: u16| ((raw_move >> shift) & 0b111) as u8;
+
+    let to = Coord::make(field(3), field(0));
+    let from = Coord::make(field(9), field(6));
+    let promotion = match field(12) {
+        0 => None,
+        1 => Some(Piece::Knight),
+        2 => Some(Piece::Bishop),
+        3 => Some(Piece::Rook),
+        4 => Some(Piece::Queen),
+        _ => return None,
+    };
+
+    position.can_move(from, to, promotion).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{CastleSide, MoveKind};
+
+    fn entry(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(raw_move.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend(0u32.to_be_bytes());
+        bytes
+    }
+
+    /// Polyglot's packing of a move given as from and to squares
+    fn raw_move(from: &str, to: &str) -> u16 {
+        let from = Coord::from_algebraic(from).unwrap();
+        let to = Coord::from_algebraic(to).unwrap();
+        (from.row as u16) << 9 | (from.col as u16) << 6 | (to.row as u16) << 3 | to.col as u16
+    }
+
+    #[test]
+    fn polyglot_keys_match_the_reference_values() {
+        let mut position = Position::default();
+        assert_eq!(position.polyglot_key(), 0x463b96181691fc9c);
+
+        let expected = [
+            ("e2", "e4", 0x823c9b50fd114196),
+            ("d7", "d5", 0x0756b94461c50fb0),
+            ("e4", "e5", 0x662fafb965db29d4),
+            ("f7", "f5", 0x22a48b5a8e47ff78),
+        ];
+        for (from, to, key) in expected {
+            let from = Coord::from_algebraic(from).unwrap();
+            let to = Coord::from_algebraic(to).unwrap();
+            position.try_move(from, to, None).unwrap();
+            assert_eq!(position.polyglot_key(), key, "after {from}{to}");
+        }
+    }
+
+    #[test]
+    fn tiny_book_gives_the_start_moves_by_weight() {
+        let start = Position::default().polyglot_key();
+
+        // Deliberately out of key order, with an entry for some other position
+        let mut bytes = entry(start, raw_move("d2", "d4"), 5);
+        bytes.extend(entry(u64::MAX, raw_move("e7", "e5"), 9));
+        bytes.extend(entry(start, raw_move("e2", "e4"), 10));
+        bytes.extend(entry(0, raw_move("g1", "f3"), 1));
+
+        let book = PolyglotBook::from_bytes(&bytes).unwrap();
+        let moves: Vec<(String, u16)> = book
+            .moves(&Position::default())
+            .into_iter()
+            .map(|(piece_move, weight)| (piece_move.to_uci(), weight))
+            .collect();
+
+        assert_eq!(moves, [("e2e4".to_string(), 10), ("d2d4".to_string(), 5)]);
+    }
+
+    #[test]
+    fn castling_is_stored_as_king_takes_rook() {
+        let position = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let book =
+            PolyglotBook::from_bytes(&entry(position.polyglot_key(), raw_move("e1", "h1"), 1))
+                .unwrap();
+
+        let moves = book.moves(&position);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].0.kind(), MoveKind::Castle(CastleSide::King));
+    }
+
+    #[test]
+    fn partial_entries_are_rejected() {
+        let bytes = entry(0, 0, 0);
+        assert_eq!(
+            PolyglotBook::from_bytes(&bytes[..15]),
+            Err(BookError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::position::{MoveInfo, Position};
+
+pub trait Engine {
+    fn choose_move(&mut self, pos: &Position) -> Option<MoveInfo>;
+}
+
+/// Plays a uniformly random legal move, reproducible for a given seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomEngine {
+    state: u64,
+}
+
+impl RandomEngine {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // splitmix64
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl Engine for RandomEngine {
+    fn choose_move(&mut self, pos: &Position) -> Option<MoveInfo> {
+        let moves = pos.legal_moves();
+        if moves.is_empty() {
+            return None;
+        }
+
+        let index = (self.next_u64() % moves.len() as u64) as usize;
+        Some(moves[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_out(seed: u64) -> Vec<MoveInfo> {
+        let mut engine = RandomEngine::new(seed);
+        let mut position = Position::default();
+        let mut moves = vec![];
+
+        for _ in 0..20 {
+            let Some(piece_move) = engine.choose_move(&position) else {
+                break;
+            };
+            position.make_move(&piece_move);
+            moves.push(piece_move);
+        }
+
+        moves
+    }
+
+    #[test]
+    fn seeded_engine_is_deterministic() {
+        assert_eq!(play_out(7), play_out(7));
+        assert_ne!(play_out(7), play_out(8));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::board::{Color, Direction, Piece, Square};
+use crate::position::{Position, State};
+
+/// Score for delivering checkmate, before any adjustment for distance to mate
+pub const MATE_SCORE: i32 = 100_000;
+
+/// Centipawns per available move
+const MOBILITY_WEIGHT: i32 = 2;
+
+/// Penalty for each square next to the king that the opponent attacks
+const KING_ZONE_ATTACK_PENALTY: i32 = 8;
+
+/// Penalty for each file beside or in front of the king without a shielding pawn
+const MISSING_SHELTER_PENALTY: i32 = 15;
+
+// Piece-square tables, listed from rank 8 down to rank 1 from White's point of view
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+pub fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 100,
+        Piece::Knight => 320,
+        Piece::Bishop => 330,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 0,
+    }
+}
+
+impl Position {
+    /// Total value of `color`'s pieces in centipawns
+    pub fn material(&self, color: Color) -> i32 {
+        Piece::ALL
+            .into_iter()
+            .map(|piece| piece_value(piece) * self.board().count(piece, color) as i32)
+            .sum()
+    }
+
+    /// White's material minus Black's, in centipawns
+    pub fn material_balance(&self) -> i32 {
+        self.material(Color::White) - self.material(Color::Black)
+    }
+
+    /// Static evaluation in centipawns from White's perspective
+    pub fn evaluate(&self) -> i32 {
+        match self.state() {
+            State::Checkmate(Color::White) => return -MATE_SCORE,
+            State::Checkmate(Color::Black) => return MATE_SCORE,
+            State::Stalemate(_) | State::Draw(_) => return 0,
+            State::Playing => {}
+        }
+
+        let mut score = 0;
+
+        for (coord, piece, color) in self.board().pieces() {
+            let value = piece_value(piece) + square_value(piece, color, coord.row, coord.col);
+
+            match color {
+                Color::White => score += value,
+                Color::Black => score -= value,
+            }
+        }
+
+        let mobility = self.mobility(Color::White) as i32 - self.mobility(Color::Black) as i32;
+        score += mobility * MOBILITY_WEIGHT;
+
+        score += self.king_safety(Color::White) - self.king_safety(Color::Black);
+
+        score
+    }
+
+    /// Penalty in centipawns, zero at best, for attacks around `color`'s king and gaps in
+    /// its pawn shelter
+    pub fn king_safety(&self, color: Color) -> i32 {
+        let king = self.king_coord(color);
+        let attacked = self.attacked_squares(color.opponent());
+
+        let zone_attacks = Direction::COMPASS
+            .into_iter()
+            .filter_map(|direction| king.offset(direction))
+            .chain([king])
+            .filter(|coord| attacked & 1 << (coord.row * 8 + coord.col) != 0)
+            .count() as i32;
+
+        // A shielding pawn stands one or two ranks ahead of the king on its own or a
+        // neighbouring file
+        let forward = match color {
+            Color::White => Direction::North,
+            Color::Black => Direction::South,
+        };
+        let pawn = Square::Piece(Piece::Pawn, color);
+
+        let missing_shelter = [
+            king.offset(Direction::West),
+            Some(king),
+            king.offset(Direction::East),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|&file| {
+            let one = file.offset(forward);
+            let two = one.and_then(|coord| coord.offset(forward));
+            ![one, two]
+                .into_iter()
+                .flatten()
+                .any(|coord| self.board()[coord] == pawn)
+        })
+        .count() as i32;
+
+        -(zone_attacks * KING_ZONE_ATTACK_PENALTY + missing_shelter * MISSING_SHELTER_PENALTY)
+    }
+}
+
+fn square_value(piece: Piece, color: Color, row: u8, col: u8) -> i32 {
+    let table = match piece {
+        Piece::Pawn => &PAWN_TABLE,
+        Piece::Knight => &KNIGHT_TABLE,
+        Piece::Bishop => &BISHOP_TABLE,
+        Piece::Rook => &ROOK_TABLE,
+        Piece::Queen => &QUEEN_TABLE,
+        Piece::King => &KING_TABLE,
+    };
+
+    // Tables are laid out for White, so Black reads them upside down
+    let rank = match color {
+        Color::White => 7 - row,
+        Color::Black => row,
+    };
+
+    table[(rank * 8 + col) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_position_is_level() {
+        assert_eq!(Position::default().evaluate(), 0);
+    }
+
+    #[test]
+    fn extra_queen_is_worth_about_nine_pawns() {
+        let position =
+            Position::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        let score = position.evaluate();
+        assert!((800..=1000).contains(&score), "scored {score}");
+    }
+
+    #[test]
+    fn material_balance() {
+        let start = Position::default();
+        assert_eq!(start.material(Color::White), 4000);
+        assert_eq!(start.material_balance(), 0);
+
+        let rook_up =
+            Position::from_fen("1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1").unwrap();
+        assert_eq!(rook_up.material_balance(), 500);
+    }
+
+    #[test]
+    fn castled_king_is_safer_than_an_exposed_one() {
+        let castled = Position::from_fen("r4rk1/ppp2ppp/8/8/8/8/PPP2PPP/R4RK1 w - - 0 1").unwrap();
+        let exposed = Position::from_fen("r4rk1/ppp2ppp/8/8/8/4K3/PPP5/R4R2 w - - 0 1").unwrap();
+
+        assert_eq!(castled.king_safety(Color::White), 0);
+        assert!(exposed.king_safety(Color::White) < castled.king_safety(Color::White));
+    }
+
+    #[test]
+    fn mirrored_positions_evaluate_to_the_negation() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let position = Position::from_fen(fen).unwrap();
+            let mirror = position.mirror_vertical();
+
+            assert_eq!(mirror.evaluate(), -position.evaluate(), "{fen}");
+            assert_eq!(mirror.to_play(), position.to_play().opponent());
+            assert_eq!(mirror.mirror_vertical(), position, "{fen}");
+        }
+
+        let mirror = Position::from_fen(fens[1]).unwrap().mirror_vertical();
+        assert_eq!(
+            mirror.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/8/3PpP2/8/PPP1P1PP/RNBQKBNR b KQkq f3 0 3"
+        );
+    }
+}
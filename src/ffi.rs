@@ -0,0 +1,190 @@
+//! C interface over `Position`, handed out as an opaque pointer.
+//!
+//! Positions come from `chess_position_new` or `chess_position_from_fen` and belong to
+//! the caller until passed to `chess_position_free`, exactly once. Strings going in are
+//! borrowed for the duration of the call, and nothing returned needs freeing apart from
+//! the position itself.
+//!
+//! Build the shared library with `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use crate::position::Position;
+
+/// A new position at the standard starting setup
+#[no_mangle]
+pub extern "C" fn chess_position_new() -> *mut Position {
+    Box::into_raw(Box::new(Position::standard()))
+}
+
+/// A new position parsed from a NUL-terminated FEN string, or null if it doesn't parse
+///
+/// # Safety
+///
+/// `fen` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_position_from_fen(fen: *const c_char) -> *mut Position {
+    if fen.is_null() {
+        return ptr::null_mut();
+    }
+
+    let position = CStr::from_ptr(fen)
+        .to_str()
+        .ok()
+        .and_then(|fen| Position::from_fen(fen).ok());
+
+    match position {
+        Some(position) => Box::into_raw(Box::new(position)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Releases a position, doing nothing for null
+///
+/// # Safety
+///
+/// `position` must be null or come from this module and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn chess_position_free(position: *mut Position) {
+    if !position.is_null() {
+        drop(Box::from_raw(position));
+    }
+}
+
+/// Plays a move such as `e2e4` or `e7e8q`, returning false and leaving the position
+/// untouched if it is malformed or illegal
+///
+/// # Safety
+///
+/// `position` must be a live position from this module and `uci` null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_position_play_uci(
+    position: *mut Position,
+    uci: *const c_char,
+) -> bool {
+    let Some(position) = position.as_mut() else {
+        return false;
+    };
+    if uci.is_null() {
+        return false;
+    }
+    let Ok(uci) = CStr::from_ptr(uci).to_str() else {
+        return false;
+    };
+
+    match position.parse_uci_move(uci) {
+        Ok(piece_move) => position
+            .try_move(
+                piece_move.from().0,
+                piece_move.to().0,
+                piece_move.promotion(),
+            )
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Writes the position's FEN into `buffer` as a NUL-terminated string, returning the
+/// FEN's length without the terminator. Like `snprintf`, nothing is written unless the
+/// whole string and terminator fit in `len` bytes, so a return value of `len` or more
+/// means the buffer was too small
+///
+/// # Safety
+///
+/// `position` must be a live position from this module and `buffer` valid for writes
+/// of `len` bytes, or null when `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn chess_position_fen(
+    position: *const Position,
+    buffer: *mut c_char,
+    len: usize,
+) -> usize {
+    let Some(position) = position.as_ref() else {
+        return 0;
+    };
+    let fen = position.to_fen();
+
+    if !buffer.is_null() && fen.len() < len {
+        ptr::copy_nonoverlapping(fen.as_ptr().cast(), buffer, fen.len());
+        *buffer.add(fen.len()) = 0;
+    }
+
+    fen.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn fen_of(position: *const Position) -> String {
+        let mut buffer = [0 as c_char; 128];
+        let len = unsafe { chess_position_fen(position, buffer.as_mut_ptr(), buffer.len()) };
+        assert!(len < buffer.len());
+
+        unsafe { CStr::from_ptr(buffer.as_ptr()) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn round_trip_through_the_c_interface() {
+        let position = chess_position_new();
+        assert_eq!(
+            fen_of(position),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        unsafe {
+            assert!(chess_position_play_uci(position, c"e2e4".as_ptr()));
+            assert!(!chess_position_play_uci(position, c"e2e4".as_ptr()));
+            assert!(!chess_position_play_uci(position, ptr::null()));
+        }
+        let fen = fen_of(position);
+        assert_eq!(
+            fen,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+
+        let copy = unsafe { chess_position_from_fen(CString::new(fen.clone()).unwrap().as_ptr()) };
+        assert!(!copy.is_null());
+        assert_eq!(fen_of(copy), fen);
+
+        unsafe {
+            chess_position_free(copy);
+            chess_position_free(position);
+            chess_position_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn short_buffers_are_left_alone() {
+        let position = chess_position_new();
+        let mut buffer = [1 as c_char; 8];
+
+        let len = unsafe { chess_position_fen(position, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(len, 56);
+        assert!(buffer.iter().all(|&c| c == 1));
+        assert_eq!(
+            unsafe { chess_position_fen(position, ptr::null_mut(), 0) },
+            56
+        );
+
+        unsafe { chess_position_free(position) };
+    }
+
+    #[test]
+    fn bad_fens_give_null() {
+        for fen in [
+            c"not a fen",
+            c"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            c"99999999999999999999999999999999/8/8/8/8/8/8/8 w - - 0 1",
+        ] {
+            assert!(unsafe { chess_position_from_fen(fen.as_ptr()) }.is_null());
+        }
+        assert!(unsafe { chess_position_from_fen(ptr::null()) }.is_null());
+    }
+}
@@ -0,0 +1,364 @@
+mod clock;
+mod pgn;
+
+pub use clock::Clock;
+pub use pgn::PgnError;
+
+use alloc::{string::String, string::ToString, vec, vec::Vec};
+use core::time::Duration;
+
+use crate::board::{Color, Coord, Piece};
+use crate::position::{DrawReason, MoveErr, MoveInfo, Position, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum GameResult {
+    #[default]
+    Ongoing,
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    start: Position,
+    position: Position,
+    moves: Vec<MoveInfo>,
+    result: GameResult,
+    tags: Vec<(String, String)>,
+    /// Zobrist keys of the positions since the last capture or pawn move, including the
+    /// current one, as nothing before those can ever recur
+    keys: Vec<u64>,
+    clock: Option<Clock>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::from_position(Position::default())
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_position(position: Position) -> Self {
+        let mut game = Self {
+            start: position.clone(),
+            keys: vec![position.zobrist()],
+            position,
+            moves: vec![],
+            result: GameResult::Ongoing,
+            tags: vec![],
+            clock: None,
+        };
+        game.update_result();
+        game
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn moves(&self) -> &[MoveInfo] {
+        &self.moves
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Plays a move for the side to move. Once the game has a result no further moves
+    /// are accepted, even where the position itself would allow one
+    pub fn play(
+        &mut self,
+        from: Coord,
+        to: Coord,
+        promotion: Option<Piece>,
+    ) -> Result<MoveInfo, MoveErr> {
+        if self.result != GameResult::Ongoing {
+            return Err(MoveErr::GameOver);
+        }
+
+        let piece_move = self.position.try_move(from, to, promotion)?;
+
+        let pawn_move = piece_move.from().1.piece_kind() == Some(Piece::Pawn);
+        if pawn_move || piece_move.captures().is_some() {
+            self.keys.clear();
+        }
+        self.keys.push(self.position.zobrist());
+
+        self.moves.push(piece_move);
+        self.update_result();
+
+        Ok(piece_move)
+    }
+
+    /// Plays a move like `play`, charging the mover `elapsed` on the clock if there is
+    /// one. Running out of time loses the game, unless the opponent has only their king
+    /// left and so could never have mated, which draws. A result the move itself brings
+    /// about, such as mate or stalemate, takes precedence over the flag. Either way the
+    /// result is final
+    pub fn play_timed(
+        &mut self,
+        from: Coord,
+        to: Coord,
+        promotion: Option<Piece>,
+        elapsed: Duration,
+    ) -> Result<MoveInfo, MoveErr> {
+        let player = self.position.to_play();
+        let piece_move = self.play(from, to, promotion)?;
+
+        if let Some(clock) = &mut self.clock {
+            clock.on_move_made(player, elapsed);
+
+            if clock.flagged(player) && self.result == GameResult::Ongoing {
+                let opponent = player.opponent();
+                let lone_king = self.position.board().color_mask(opponent)
+                    == self.position.board().piece_mask(Piece::King, opponent);
+
+                self.result = match opponent {
+                    _ if lone_king => GameResult::Draw,
+                    Color::White => GameResult::WhiteWins,
+                    Color::Black => GameResult::BlackWins,
+                };
+            }
+        }
+
+        Ok(piece_move)
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    /// How many times the current position has occurred, counting this occurrence
+    pub fn repetition_count(&self) -> usize {
+        let current = self.keys.last().copied();
+        self.keys
+            .iter()
+            .filter(|&&key| Some(key) == current)
+            .count()
+    }
+
+    /// The position's state, except that a third occurrence of the same position draws
+    /// by repetition, which only the game's history can tell
+    pub fn state(&self) -> State {
+        match self.position.state() {
+            State::Playing if self.repetition_count() >= 3 => {
+                State::Draw(DrawReason::ThreefoldRepetition)
+            }
+            state => state,
+        }
+    }
+
+    fn update_result(&mut self) {
+        self.result = match self.state() {
+            State::Playing => GameResult::Ongoing,
+            State::Checkmate(Color::White) => GameResult::BlackWins,
+            State::Checkmate(Color::Black) => GameResult::WhiteWins,
+            State::Stalemate(_) | State::Draw(_) => GameResult::Draw,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coord(algebraic: &str) -> Coord {
+        Coord::from_algebraic(algebraic).unwrap()
+    }
+
+    fn play(game: &mut Game, moves: &[(&str, &str)]) {
+        for (from, to) in moves {
+            game.play(coord(from), coord(to), None).unwrap();
+        }
+    }
+
+    #[test]
+    fn fools_mate_is_a_win_for_black() {
+        let mut game = Game::new();
+
+        play(
+            &mut game,
+            &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")],
+        );
+
+        assert_eq!(game.result(), GameResult::BlackWins);
+        assert_eq!(game.moves().len(), 4);
+    }
+
+    #[test]
+    fn decided_games_refuse_moves() {
+        let mut game =
+            Game::from_position(Position::from_fen("4k3/4p3/8/8/8/8/8/4K2N w - - 99 80").unwrap());
+
+        play(&mut game, &[("h1", "g3")]);
+        assert_eq!(game.result(), GameResult::Draw);
+
+        // The pawn move would reset the fifty-move count, but the draw stands
+        assert_eq!(
+            game.play(coord("e7"), coord("e5"), None),
+            Err(MoveErr::GameOver)
+        );
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(game.moves().len(), 1);
+    }
+
+    #[test]
+    fn threefold_repetition_is_a_draw() {
+        let mut game = Game::new();
+        let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 2);
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(game.state(), State::Draw(DrawReason::ThreefoldRepetition));
+        assert_eq!(
+            game.play(coord("g1"), coord("f3"), None),
+            Err(MoveErr::GameOver)
+        );
+    }
+
+    #[test]
+    fn captures_reset_the_repetition_window() {
+        let mut game =
+            Game::from_position(Position::from_fen("4k3/p7/8/8/8/8/8/R3K1N1 w - - 0 1").unwrap());
+        let shuffle = [("g1", "f3"), ("e8", "d8"), ("f3", "g1"), ("d8", "e8")];
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 2);
+
+        // Nothing from before the capture can come round again
+        play(&mut game, &[("a1", "a7")]);
+        assert_eq!(game.repetition_count(), 1);
+
+        play(&mut game, &[("e8", "d8"), ("a7", "a1"), ("d8", "e8")]);
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 2);
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        // Knight back on g1 with the black king on d8 is the third time since the capture
+        play(&mut game, &shuffle[..3]);
+        assert_eq!(game.result(), GameResult::Draw);
+    }
+
+    fn timed_game(fen: &str, base: u64) -> Game {
+        let mut game = Game::from_position(Position::from_fen(fen).unwrap());
+        game.set_clock(Some(Clock::new(
+            Duration::from_secs(base),
+            Duration::from_secs(1),
+        )));
+        game
+    }
+
+    #[test]
+    fn timed_moves_charge_the_clock() {
+        let mut game = timed_game(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            60,
+        );
+
+        for (from, to, secs) in [("e2", "e4", 3), ("e7", "e5", 10), ("g1", "f3", 4)] {
+            game.play_timed(coord(from), coord(to), None, Duration::from_secs(secs))
+                .unwrap();
+        }
+
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(55));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(51));
+        assert_eq!(game.result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn flag_fall_loses_for_good() {
+        let mut game = timed_game("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1", 10);
+
+        game.play_timed(coord("e2"), coord("e4"), None, Duration::from_secs(12))
+            .unwrap();
+        assert!(game.clock().unwrap().flagged(Color::White));
+        assert_eq!(game.result(), GameResult::BlackWins);
+
+        // The game is over, even though the position itself plays on
+        assert_eq!(
+            game.play_timed(coord("e7"), coord("e5"), None, Duration::from_secs(1)),
+            Err(MoveErr::GameOver)
+        );
+        assert_eq!(game.result(), GameResult::BlackWins);
+        assert_eq!(game.moves().len(), 1);
+    }
+
+    #[test]
+    fn flag_fall_against_a_lone_king_is_a_draw() {
+        let mut game = timed_game("4k3/8/8/8/8/8/8/R3K3 b - - 0 1", 10);
+
+        game.play_timed(coord("e8"), coord("d8"), None, Duration::from_secs(1))
+            .unwrap();
+        game.play_timed(coord("a1"), coord("a7"), None, Duration::from_secs(30))
+            .unwrap();
+
+        assert!(game.clock().unwrap().flagged(Color::White));
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(
+            game.play(coord("d8"), coord("e8"), None),
+            Err(MoveErr::GameOver)
+        );
+    }
+
+    #[test]
+    fn flag_fall_against_a_lone_minor_still_loses() {
+        let mut game = timed_game("4k3/8/8/8/8/8/8/R3K1n1 w - - 0 1", 10);
+
+        game.play_timed(coord("a1"), coord("a7"), None, Duration::from_secs(30))
+            .unwrap();
+
+        assert_eq!(game.result(), GameResult::BlackWins);
+    }
+
+    #[test]
+    fn mate_stands_even_when_the_flag_falls() {
+        let mut game = timed_game("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", 10);
+
+        game.play_timed(coord("a1"), coord("a8"), None, Duration::from_secs(30))
+            .unwrap();
+
+        assert!(game.clock().unwrap().flagged(Color::White));
+        assert_eq!(game.result(), GameResult::WhiteWins);
+    }
+
+    #[test]
+    fn stalemate_stands_even_when_the_flag_falls() {
+        // Black's pawn is blocked, so Qc7 leaves it without a move
+        let mut game = timed_game("k7/p7/P7/2Q5/8/8/8/4K3 w - - 0 1", 10);
+
+        game.play_timed(coord("c5"), coord("c7"), None, Duration::from_secs(30))
+            .unwrap();
+
+        assert!(game.clock().unwrap().flagged(Color::White));
+        assert_eq!(game.result(), GameResult::Draw);
+    }
+}
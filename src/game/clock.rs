@@ -0,0 +1,97 @@
+use core::time::Duration;
+
+use crate::board::Color;
+
+/// Chess clock for a Fischer time control: both sides start with the same base time and
+/// gain a fixed increment after each move made in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Clock {
+    remaining: (Duration, Duration),
+    increment: Duration,
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    pub fn new(base: Duration, increment: Duration) -> Self {
+        Self {
+            remaining: (base, base),
+            increment,
+            flagged: None,
+        }
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.remaining.0,
+            Color::Black => self.remaining.1,
+        }
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Charges `color` for a move that took `elapsed`, adding the increment if the move
+    /// was made before their time ran out
+    pub fn on_move_made(&mut self, color: Color, elapsed: Duration) {
+        let remaining = match color {
+            Color::White => &mut self.remaining.0,
+            Color::Black => &mut self.remaining.1,
+        };
+
+        match remaining.checked_sub(elapsed) {
+            Some(left) if !left.is_zero() => *remaining = left + self.increment,
+            _ => {
+                *remaining = Duration::ZERO;
+                self.flagged.get_or_insert(color);
+            }
+        }
+    }
+
+    /// Whether `color` ran out of time first. Only one side can flag, since the game is
+    /// over from then on
+    pub fn flagged(&self, color: Color) -> bool {
+        self.flagged == Some(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn increments_are_added_after_each_move() {
+        let mut clock = Clock::new(secs(60), secs(2));
+
+        clock.on_move_made(Color::White, secs(5));
+        clock.on_move_made(Color::Black, secs(10));
+        clock.on_move_made(Color::White, secs(1));
+
+        assert_eq!(clock.remaining(Color::White), secs(58));
+        assert_eq!(clock.remaining(Color::Black), secs(52));
+        assert!(!clock.flagged(Color::White));
+        assert!(!clock.flagged(Color::Black));
+    }
+
+    #[test]
+    fn running_out_of_time_flags() {
+        let mut clock = Clock::new(secs(10), secs(5));
+
+        clock.on_move_made(Color::White, secs(8));
+        assert_eq!(clock.remaining(Color::White), secs(7));
+
+        // Using up exactly the time left is already too late, and earns no increment
+        clock.on_move_made(Color::White, secs(7));
+        assert_eq!(clock.remaining(Color::White), Duration::ZERO);
+        assert!(clock.flagged(Color::White));
+
+        // Black running out later doesn't change who flagged first
+        clock.on_move_made(Color::Black, secs(11));
+        assert!(clock.flagged(Color::White));
+        assert!(!clock.flagged(Color::Black));
+    }
+}
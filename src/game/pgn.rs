@@ -0,0 +1,338 @@
+use alloc::{format, string::String, string::ToString, vec::Vec};
+use core::fmt;
+
+use crate::board::Color;
+use crate::position::{FenError, Position, SanError};
+
+use super::{Game, GameResult};
+
+const LINE_WIDTH: usize = 80;
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 6] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PgnError {
+    BadTag(String),
+    BadFen(FenError),
+    BadMove(String, SanError),
+    BadResult(String),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadTag(tag) => write!(f, "malformed tag pair `{tag}`"),
+            Self::BadFen(err) => write!(f, "bad FEN tag: {err}"),
+            Self::BadMove(token, err) => write!(f, "bad move `{token}`: {err}"),
+            Self::BadResult(result) => {
+                write!(f, "result `{result}` contradicts the final position")
+            }
+        }
+    }
+}
+
+impl core::error::Error for PgnError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::BadFen(err) => Some(err),
+            Self::BadMove(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Game {
+    pub fn to_pgn(&self) -> String {
+        let result = result_token(self.result);
+
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = self.tag(name).unwrap_or(default);
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push_str(&format!("[Result \"{result}\"]\n"));
+
+        // Any remaining tags follow the roster in the order they were set
+        for (name, value) in &self.tags {
+            let is_roster = SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name);
+            if !is_roster && !matches!(name.as_str(), "Result" | "SetUp" | "FEN") {
+                pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+            }
+        }
+
+        let fen = self.start.to_fen();
+        if fen != Position::standard().to_fen() {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{fen}\"]\n"));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut position = self.start.clone();
+
+        for (i, piece_move) in self.moves.iter().enumerate() {
+            let number = position.fullmove_number();
+
+            match position.to_play() {
+                Color::White => tokens.push(format!("{number}.")),
+                Color::Black if i == 0 => tokens.push(format!("{number}...")),
+                Color::Black => {}
+            }
+
+            tokens.push(position.move_to_san(piece_move));
+            position
+                .try_move(
+                    piece_move.from().0,
+                    piece_move.to().0,
+                    piece_move.promotion(),
+                )
+                .unwrap();
+        }
+        tokens.push(result.to_string());
+
+        // Wrap the move text so no line exceeds the export width
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        pgn
+    }
+
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+
+        for line in pgn.lines() {
+            let line = line.trim();
+
+            if line.starts_with('[') {
+                tags.push(parse_tag(line)?);
+            } else if !line.starts_with('%') {
+                // Rest-of-line comments run to the end of the line
+                let line = line.split(';').next().unwrap();
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::from_fen(fen).map_err(PgnError::BadFen)?,
+            None => Position::standard(),
+        };
+
+        let mut game = Self::from_position(start);
+        for (name, value) in &tags {
+            game.set_tag(name, value);
+        }
+
+        let mut result = None;
+
+        for token in strip_comments(&movetext).split_whitespace() {
+            if let Some(token_result) = parse_result(token) {
+                result = Some(token_result);
+                break;
+            }
+
+            let token = strip_move_number(token);
+
+            if token.is_empty() || token.starts_with('$') {
+                continue;
+            }
+
+            let piece_move = game
+                .position
+                .parse_san(token)
+                .map_err(|err| PgnError::BadMove(token.to_string(), err))?;
+
+            game.play(
+                piece_move.from().0,
+                piece_move.to().0,
+                piece_move.promotion(),
+            )
+            .map_err(|_| PgnError::BadMove(token.to_string(), SanError::Illegal))?;
+        }
+
+        // Resignations and agreed draws only show up in the result token
+        if let Some(result) = result {
+            if game.result == GameResult::Ongoing {
+                game.result = result;
+            } else if game.result != result {
+                return Err(PgnError::BadResult(result_token(result).to_string()));
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let bad_tag = || PgnError::BadTag(line.to_string());
+
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(bad_tag)?;
+
+    let (name, value) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(bad_tag)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(bad_tag)?;
+
+    Ok((name.to_string(), value.replace("\\\"", "\"")))
+}
+
+/// Removes `{...}` comments and `(...)` variations, which may nest
+fn strip_comments(movetext: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_comment = false;
+    let mut variation_depth = 0;
+
+    for c in movetext.chars() {
+        match c {
+            '{' if !in_comment => in_comment = true,
+            '}' if in_comment => {
+                in_comment = false;
+                stripped.push(' ');
+            }
+            _ if in_comment => {}
+            '(' => variation_depth += 1,
+            ')' if variation_depth > 0 => {
+                variation_depth -= 1;
+                stripped.push(' ');
+            }
+            _ if variation_depth > 0 => {}
+            _ => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+/// Drops a move number such as `12.` or `12...`, which may be glued to the move itself.
+/// Only digits followed by a period count, so `0-0` castling is left alone
+fn strip_move_number(token: &str) -> &str {
+    let digits = token.len() - token.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    match token[digits..].strip_prefix('.') {
+        Some(rest) if digits > 0 => rest.trim_start_matches('.'),
+        _ => token,
+    }
+}
+
+fn parse_result(token: &str) -> Option<GameResult> {
+    match token {
+        "*" => Some(GameResult::Ongoing),
+        "1-0" => Some(GameResult::WhiteWins),
+        "0-1" => Some(GameResult::BlackWins),
+        "1/2-1/2" => Some(GameResult::Draw),
+        _ => None,
+    }
+}
+
+fn result_token(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::WhiteWins => "1-0",
+        GameResult::BlackWins => "0-1",
+        GameResult::Draw => "1/2-1/2",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::board::Coord;
+
+    #[test]
+    fn exported_pgn_parses_back() {
+        let mut game = Game::new();
+        game.set_tag("White", "Scholar");
+
+        for (from, to) in [
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("f1", "c4"),
+            ("b8", "c6"),
+            ("d1", "h5"),
+            ("g8", "f6"),
+            ("h5", "f7"),
+        ] {
+            let from = Coord::from_algebraic(from).unwrap();
+            let to = Coord::from_algebraic(to).unwrap();
+            game.play(from, to, None).unwrap();
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"?\"]\n"));
+        assert!(pgn.contains("[White \"Scholar\"]\n"));
+        assert!(pgn.contains("[Result \"1-0\"]\n"));
+        assert!(pgn.ends_with("\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n"));
+
+        let imported = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(imported.moves(), game.moves());
+        assert_eq!(imported.result(), GameResult::WhiteWins);
+        assert_eq!(imported.tag("White"), Some("Scholar"));
+    }
+
+    #[test]
+    fn imports_a_ten_move_game() {
+        let pgn = r#"[Event "Breyer test"]
+[White "?"]
+[Black "?"]
+
+1. e4 e5 2. Nf3 Nc6 {the Ruy Lopez} 3. Bb5 a6 4. Ba4 Nf6 5. 0-0 (5. d4 exd4)
+Be7 6. Re1 b5 7. Bb3 $1 d6 8. c3 0-0 9. h3 Nb8 ; rerouting the knight
+10.d4 Nbd7 *
+"#;
+
+        let game = Game::from_pgn(pgn).unwrap();
+
+        assert_eq!(game.moves().len(), 20);
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert_eq!(game.tag("Event"), Some("Breyer test"));
+        assert_eq!(
+            game.position().to_fen(),
+            "r1bq1rk1/2pnbppp/p2p1n2/1p2p3/3PP3/1BP2N1P/PP3PP1/RNBQR1K1 w - - 1 11"
+        );
+    }
+
+    #[test]
+    fn move_numbers_are_stripped_without_touching_castles() {
+        assert_eq!(strip_move_number("12."), "");
+        assert_eq!(strip_move_number("12...Nf6"), "Nf6");
+        assert_eq!(strip_move_number("4.0-0"), "0-0");
+        assert_eq!(strip_move_number("0-0-0"), "0-0-0");
+        assert_eq!(strip_move_number("e4"), "e4");
+    }
+
+    #[test]
+    fn reports_the_offending_move() {
+        let err = Game::from_pgn("1. e4 e5 2. Ke3 *").unwrap_err();
+
+        assert_eq!(err, PgnError::BadMove("Ke3".to_string(), SanError::Illegal));
+    }
+}
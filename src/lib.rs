@@ -0,0 +1,23 @@
+// Tests always have std, so they can use its prelude even without the feature
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod board;
+#[cfg(feature = "std")]
+pub mod book;
+pub mod engine;
+pub mod eval;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod game;
+pub mod moves;
+pub mod position;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "std")]
+pub mod uci;
+#[cfg(feature = "wasm")]
+pub mod wasm;
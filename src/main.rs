@@ -1,6 +1,5 @@
-mod board;
-mod moves;
-mod position;
+use std::io;
 
-fn main() {
+fn main() -> io::Result<()> {
+    chess::uci::run(io::stdin().lock(), io::stdout().lock())
 }
@@ -1,22 +1,66 @@
-use crate::board::{Board, Color, Coord, Piece, Square, STANDARD_BOARD};
+mod builder;
+mod chess960;
+mod epd;
+pub mod fen;
+mod san;
+mod uci;
+mod zobrist;
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+
+use crate::board::{mask_coords, Board, Color, Coord, Direction, Piece, Square, STANDARD_BOARD};
+
+pub use builder::{PositionBuilder, SetupError};
+pub use epd::{EpdError, EpdOps};
+pub use fen::FenError;
+pub use san::SanError;
+pub use uci::UciLineError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CastleSide {
     King,
     Queen,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CastleRights {
     pub king: bool,
     pub queen: bool,
 }
 
+/// Files of the king and its castling rooks, which vary in Chess960
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct CastleLayout {
+    king: u8,
+    king_rook: u8,
+    queen_rook: u8,
+}
+
+const STANDARD_LAYOUT: CastleLayout = CastleLayout {
+    king: 4,
+    king_rook: 7,
+    queen_rook: 0,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     Playing,
     Checkmate(Color),
     Stalemate(Color),
+    Draw(DrawReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawReason {
+    FiftyMove,
+    ThreefoldRepetition,
+    InsufficientMaterial,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,28 +70,140 @@ pub enum MoveErr {
     KingInCheck,
     NoCastlingRight,
     PathBlocked,
+    InvalidMove,
     InvalidPromotion,
     OutOfBounds,
+    /// The game already has a result, so no more moves can be played
+    GameOver,
+}
+
+impl fmt::Display for MoveErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::PieceNotOwned => "no piece of the side to move on that square",
+            Self::DestinationOccupied => "destination occupied",
+            Self::KingInCheck => "king would be in check",
+            Self::NoCastlingRight => "castling right has been lost",
+            Self::PathBlocked => "path is blocked",
+            Self::InvalidMove => "piece cannot move that way",
+            Self::InvalidPromotion => "invalid promotion",
+            Self::OutOfBounds => "square is off the board",
+            Self::GameOver => "the game is already over",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for MoveErr {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveKind {
+    Quiet,
+    /// A pawn's two-square advance from its starting rank
+    DoublePush,
+    Capture(Piece),
+    EnPassant,
+    Castle(CastleSide),
+    Promotion {
+        piece: Piece,
+        captures: Option<Piece>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MoveInfo {
     from: (Coord, Square),
     to: (Coord, Square),
-    captures: Option<Piece>,
-    promotion: Option<Piece>,
+    kind: MoveKind,
+}
+
+impl MoveInfo {
+    pub fn from(&self) -> (Coord, Square) {
+        self.from
+    }
+
+    pub fn to(&self) -> (Coord, Square) {
+        self.to
+    }
+
+    pub fn kind(&self) -> MoveKind {
+        self.kind
+    }
+
+    pub fn captures(&self) -> Option<Piece> {
+        match self.kind {
+            MoveKind::Capture(piece) => Some(piece),
+            MoveKind::EnPassant => Some(Piece::Pawn),
+            MoveKind::Promotion { captures, .. } => captures,
+            _ => None,
+        }
+    }
+
+    pub fn promotion(&self) -> Option<Piece> {
+        match self.kind {
+            MoveKind::Promotion { piece, .. } => Some(piece),
+            _ => None,
+        }
+    }
+
+    pub fn castle(&self) -> Option<CastleSide> {
+        match self.kind {
+            MoveKind::Castle(side) => Some(side),
+            _ => None,
+        }
+    }
+}
+
+/// State that can't be recovered from the move itself when undoing it
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct HistoryEntry {
+    piece_move: MoveInfo,
+    castle_rights: (CastleRights, CastleRights),
+    en_passant: Option<Coord>,
+    halfmove_clock: u16,
+    state: State,
+    checks: Vec<Coord>,
+}
+
+/// Perft leaf counts, where each category counts leaves reached by that kind of move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct PerftResult {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+/// Everything `unmake_move` needs to take back a move played with `make_move`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoToken {
+    entry: HistoryEntry,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     board: Board,
     to_play: Color,
     castle_rights: (CastleRights, CastleRights),
+    castle_layout: CastleLayout,
+    en_passant: Option<Coord>,
+    halfmove_clock: u16,
+    fullmove_number: u32,
 
     state: State,
 
     checks: Vec<Coord>,
     king_coord: (Coord, Coord),
+
+    history: Vec<HistoryEntry>,
 }
 
 impl Default for Position {
@@ -70,13 +226,45 @@ impl Position {
                     queen: true,
                 },
             ),
+            castle_layout: STANDARD_LAYOUT,
             to_play: Color::White,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
             state: State::Playing,
             checks: vec![],
             king_coord: (Coord { row: 0, col: 4 }, Coord { row: 7, col: 4 }),
+            history: vec![],
         }
     }
 
+    /// A position with no pieces at all and no castling rights, with White to play. As
+    /// White has no legal moves it counts as stalemate, and `king_coord` reports the
+    /// kings' home squares even though nothing stands there
+    pub fn empty() -> Self {
+        let no_rights = CastleRights {
+            king: false,
+            queen: false,
+        };
+
+        let mut position = Self {
+            board: Board::empty(),
+            castle_rights: (no_rights, no_rights),
+            castle_layout: STANDARD_LAYOUT,
+            to_play: Color::White,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            state: State::Playing,
+            checks: vec![],
+            king_coord: (Coord::make(0, 4), Coord::make(7, 4)),
+            history: vec![],
+        };
+        position.update_state();
+
+        position
+    }
+
     pub fn board(&self) -> &Board {
         &self.board
     }
@@ -89,6 +277,10 @@ impl Position {
         self.to_play
     }
 
+    pub fn history(&self) -> impl Iterator<Item = MoveInfo> + '_ {
+        self.history.iter().map(|entry| entry.piece_move)
+    }
+
     pub fn castle_rights(&self, player: Color) -> CastleRights {
         match player {
             Color::White => self.castle_rights.0,
@@ -103,6 +295,20 @@ impl Position {
         }
     }
 
+    pub fn en_passant(&self) -> Option<Coord> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Square of `player`'s king. Only `Position::empty()` can lack a king, in which
+    /// case this is the king's home square rather than anything on the board
     pub fn king_coord(&self, player: Color) -> Coord {
         match player {
             Color::White => self.king_coord.0,
@@ -121,39 +327,72 @@ impl Position {
         !self.checks.is_empty()
     }
 
-    pub fn get_attackers(&self, coord: Coord, player: Color) -> Vec<Coord> {
-        let mut attackers = Vec::new();
-        let opponent = match player {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+    /// Squares of the pieces giving check to the side to play
+    pub fn checkers(&self) -> &[Coord] {
+        &self.checks
+    }
 
-        for row in 0..8 {
-            for col in 0..8 {
-                let piece_coord = Coord { row, col };
+    /// Whether two pieces give check at once, so only a king move can answer it
+    pub fn is_double_check(&self) -> bool {
+        self.checks.len() >= 2
+    }
 
-                // Check if the piece belongs to the opponent
-                if let Some(Square::Piece(piece, color)) = self.board.square(piece_coord) {
-                    if color == opponent {
-                        // Check if this piece can attack the given `coord`
-                        if can_piece_attack(self.board, piece_coord, piece, color, coord) {
-                            attackers.push(piece_coord);
-                        }
-                    }
+    pub fn is_checkmate(&self) -> bool {
+        self.is_in_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check() && self.legal_moves().is_empty()
+    }
+
+    /// Whether neither side can ever mate: a lone minor piece at most, or any number of
+    /// bishops for either side as long as they all stand on squares of one shade
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = 0;
+        let mut knights = false;
+        let mut bishop_shades = [false; 2];
+
+        for (coord, piece, _) in self.board.pieces() {
+            match piece {
+                Piece::King => {}
+                Piece::Knight => {
+                    minors += 1;
+                    knights = true;
+                }
+                Piece::Bishop => {
+                    minors += 1;
+                    bishop_shades[coord.square_color() as usize] = true;
                 }
+                // Any pawn, rook or queen can still deliver mate
+                Piece::Pawn | Piece::Rook | Piece::Queen => return false,
             }
         }
 
-        todo!()
+        // Bishops confined to one shade can never cover the king's flight squares of
+        // the other, however many there are
+        minors <= 1 || !knights && bishop_shades != [true, true]
+    }
+
+    pub fn get_attackers(&self, coord: Coord, player: Color) -> Vec<Coord> {
+        get_attackers(&self.board, coord, player)
     }
 
     pub fn is_square_attacked(&self, coord: Coord, player: Color) -> bool {
         !self.get_attackers(coord, player).is_empty()
     }
 
+    /// Mask of every square attacked by `by`, with bit `row * 8 + col` set for each
+    pub fn attacked_squares(&self, by: Color) -> u64 {
+        attacked_squares(&self.board, by)
+    }
+
     pub fn can_castle(&self, side: CastleSide) -> Option<MoveErr> {
-        let player = self.to_play();
+        self.castle_status(self.to_play(), side).err()
+    }
 
+    /// Whether `player` could castle on `side` if it were their move, or the first reason
+    /// they can't. Works for either side, unlike `can_castle`
+    pub fn castle_status(&self, player: Color, side: CastleSide) -> Result<(), MoveErr> {
         let rights = self.castle_rights(player);
 
         let has_right = match side {
@@ -162,42 +401,53 @@ impl Position {
         };
 
         if !has_right {
-            return Some(MoveErr::NoCastlingRight);
+            return Err(MoveErr::NoCastlingRight);
         }
 
-        if self.is_in_check() {
-            return Some(MoveErr::KingInCheck);
+        if self.is_square_attacked(self.king_coord(player), player) {
+            return Err(MoveErr::KingInCheck);
         }
 
-        let (row, king_col, rook_col) = match (player, side) {
-            (Color::White, CastleSide::King) => (0, 4, 7),
-            (Color::White, CastleSide::Queen) => (0, 4, 0),
-            (Color::Black, CastleSide::King) => (7, 4, 7),
-            (Color::Black, CastleSide::Queen) => (7, 4, 0),
-        };
+        let (king_from, king_to, rook_from, rook_to) = self.castle_coords(player, side);
 
-        let cols = if king_col < rook_col {
-            king_col + 1..rook_col
-        } else {
-            rook_col + 1..king_col
-        };
+        if self.board.square(king_from) != Some(Square::Piece(Piece::King, player))
+            || self.board.square(rook_from) != Some(Square::Piece(Piece::Rook, player))
+        {
+            return Err(MoveErr::NoCastlingRight);
+        }
 
-        for col in cols {
-            let coord = Coord { row, col };
-            let square = self.board.square(coord).unwrap();
-            if !square.is_empty() {
-                return Some(MoveErr::PathBlocked);
+        // Everything the king and rook cross must be empty, apart from themselves
+        let cols = [king_from.col, king_to.col, rook_from.col, rook_to.col];
+        let (min_col, max_col) = (*cols.iter().min().unwrap(), *cols.iter().max().unwrap());
+
+        for col in min_col..=max_col {
+            let coord = Coord::make(king_from.row, col);
+            let square = self.board[coord];
+            if coord != king_from && coord != rook_from && !square.is_empty() {
+                return Err(MoveErr::PathBlocked);
             }
         }
 
-        for col in king_col..=rook_col {
-            let coord = Coord { row, col };
-            if self.is_square_attacked(coord, player) {
-                return Some(MoveErr::KingInCheck);
+        // The king may not pass through an attacked square
+        let (min_col, max_col) = (
+            king_from.col.min(king_to.col),
+            king_from.col.max(king_to.col),
+        );
+        let attacked = self.attacked_squares(player.opponent());
+        for col in min_col + 1..max_col {
+            if attacked & 1 << (king_from.row * 8 + col) != 0 {
+                return Err(MoveErr::KingInCheck);
             }
         }
 
-        None
+        // Nor land on one, which is checked after the rook has moved out of the way
+        let mut board = self.board;
+        castle_board(&mut board, player, (king_from, king_to, rook_from, rook_to));
+        if !get_attackers(&board, king_to, player).is_empty() {
+            return Err(MoveErr::KingInCheck);
+        }
+
+        Ok(())
     }
 
     pub fn try_castle(&mut self, side: CastleSide) -> Result<(), MoveErr> {
@@ -205,51 +455,179 @@ impl Position {
             return Err(err);
         }
 
+        let piece_move = self.castle_move(side);
+        self.push_history(piece_move);
+        self.apply_move(&piece_move);
+
+        Ok(())
+    }
+
+    pub fn can_move(
+        &self,
+        from: Coord,
+        to: Coord,
+        promotion: Option<Piece>,
+    ) -> Result<MoveInfo, MoveErr> {
+        let piece_move = self.pseudo_legal_move(from, to, promotion)?;
+
+        if !self.is_king_safe_after(&piece_move) {
+            return Err(MoveErr::KingInCheck);
+        }
+
+        Ok(piece_move)
+    }
+
+    /// Validates a move by how the piece moves, without checking whether it exposes the
+    /// king. Castling is the exception, as its rules already cover attacked squares
+    fn pseudo_legal_move(
+        &self,
+        from: Coord,
+        to: Coord,
+        promotion: Option<Piece>,
+    ) -> Result<MoveInfo, MoveErr> {
         let player = self.to_play();
 
-        let (king_from, king_to, rook_from, rook_to) = match (player, side) {
-            (Color::White, CastleSide::King) => (
-                Coord { row: 0, col: 4 },
-                Coord { row: 0, col: 6 },
-                Coord { row: 0, col: 7 },
-                Coord { row: 0, col: 5 },
-            ),
-            (Color::White, CastleSide::Queen) => (
-                Coord { row: 0, col: 4 },
-                Coord { row: 0, col: 2 },
-                Coord { row: 0, col: 0 },
-                Coord { row: 0, col: 3 },
-            ),
-            (Color::Black, CastleSide::King) => (
-                Coord { row: 7, col: 4 },
-                Coord { row: 7, col: 6 },
-                Coord { row: 7, col: 7 },
-                Coord { row: 7, col: 5 },
-            ),
-            (Color::Black, CastleSide::Queen) => (
-                Coord { row: 7, col: 4 },
-                Coord { row: 7, col: 2 },
-                Coord { row: 7, col: 0 },
-                Coord { row: 7, col: 3 },
-            ),
+        let from_square = self.board.square(from).ok_or(MoveErr::OutOfBounds)?;
+        let to_square = self.board.square(to).ok_or(MoveErr::OutOfBounds)?;
+
+        let piece = match from_square {
+            Square::Piece(piece, color) if color == player => piece,
+            _ => return Err(MoveErr::PieceNotOwned),
         };
 
-        self.board.move_piece(king_from, king_to);
-        self.board.move_piece(rook_from, rook_to);
+        // Castling is expressed either as the king moving two squares along its home row,
+        // or as the king moving onto its own rook as Chess960 requires
+        if piece == Piece::King && from == self.king_home(player) {
+            let side = [CastleSide::King, CastleSide::Queen]
+                .into_iter()
+                .find(|&side| {
+                    let (_, king_to, rook_from, _) = self.castle_coords(player, side);
+                    let onto_rook =
+                        to == rook_from && to_square == Square::Piece(Piece::Rook, player);
+                    onto_rook || (to == king_to && from.col.abs_diff(to.col) == 2)
+                });
+
+            if let Some(side) = side {
+                if promotion.is_some() {
+                    return Err(MoveErr::InvalidPromotion);
+                }
+                if let Some(err) = self.can_castle(side) {
+                    return Err(err);
+                }
 
-        *self.king_coord_mut(player) = king_to;
-        *self.castle_rights_mut(player) = CastleRights {
-            king: false,
-            queen: false,
+                return Ok(self.castle_move(side));
+            }
+        }
+
+        let captures = match to_square {
+            Square::Empty => None,
+            Square::Piece(_, color) if color == player => return Err(MoveErr::DestinationOccupied),
+            Square::Piece(captured, _) => Some(captured),
         };
 
-        self.next_move();
+        if piece == Piece::Pawn && from.col == to.col {
+            // Pushes only move forward onto empty squares
+            if !can_pawn_push(from, to, player) {
+                return Err(MoveErr::InvalidMove);
+            }
+            if captures.is_some() {
+                return Err(MoveErr::PathBlocked);
+            }
 
-        Ok(())
+            // A double push also needs the square it passes over to be empty
+            let between = Coord {
+                row: (from.row + to.row) / 2,
+                col: from.col,
+            };
+            if from.row.abs_diff(to.row) == 2 && !self.board[between].is_empty() {
+                return Err(MoveErr::PathBlocked);
+            }
+        } else if piece == Piece::Pawn && captures.is_none() {
+            // Diagonal pawn moves must capture, possibly en passant
+            if !can_pawn_attack(from, to, player) || self.en_passant != Some(to) {
+                return Err(MoveErr::InvalidMove);
+            }
+        } else if !can_piece_attack(&self.board, from, piece, player, to) {
+            return Err(if can_piece_reach(from, piece, player, to) {
+                MoveErr::PathBlocked
+            } else {
+                MoveErr::InvalidMove
+            });
+        }
+
+        let last_row = match player {
+            Color::White => 7,
+            Color::Black => 0,
+        };
+
+        match (piece, promotion) {
+            (Piece::Pawn, None) if to.row == last_row => return Err(MoveErr::InvalidPromotion),
+            (Piece::Pawn, Some(Piece::Pawn | Piece::King)) => {
+                return Err(MoveErr::InvalidPromotion)
+            }
+            (Piece::Pawn, Some(_)) if to.row != last_row => return Err(MoveErr::InvalidPromotion),
+            (Piece::Pawn, _) | (_, None) => {}
+            (_, Some(_)) => return Err(MoveErr::InvalidPromotion),
+        }
+
+        let kind = match (promotion, captures) {
+            (Some(piece), captures) => MoveKind::Promotion { piece, captures },
+            (None, Some(captured)) => MoveKind::Capture(captured),
+            (None, None) if piece == Piece::Pawn && from.col != to.col => MoveKind::EnPassant,
+            (None, None) if piece == Piece::Pawn && from.row.abs_diff(to.row) == 2 => {
+                MoveKind::DoublePush
+            }
+            (None, None) => MoveKind::Quiet,
+        };
+
+        Ok(MoveInfo {
+            from: (from, from_square),
+            to: (to, to_square),
+            kind,
+        })
+    }
+
+    /// Whether the king is out of check once `piece_move` has been played
+    fn is_king_safe_after(&self, piece_move: &MoveInfo) -> bool {
+        if piece_move.castle().is_some() {
+            return true;
+        }
+
+        let player = self.to_play();
+        let (from, from_square) = piece_move.from;
+        let to = piece_move.to.0;
+        let piece = from_square.piece_kind().unwrap();
+
+        // Kings may never stand next to each other
+        if piece == Piece::King && self.king_coord(player.opponent()).chebyshev_distance(to) == 1 {
+            return false;
+        }
+
+        let mut board = self.board;
+        board.move_piece(from, to);
+
+        if piece_move.kind == MoveKind::EnPassant {
+            board.set_square(
+                Coord {
+                    row: from.row,
+                    col: to.col,
+                },
+                Square::Empty,
+            );
+        }
+
+        let king_coord = if piece == Piece::King {
+            to
+        } else {
+            self.king_coord(player)
+        };
+
+        get_attackers(&board, king_coord, player).is_empty()
     }
 
-    pub fn can_move(&self, from: Coord, to: Coord, promotion: Option<Piece>) -> Result<MoveInfo, MoveErr> {
-        todo!()
+    /// Whether `can_move` would accept the move, for callers that only need a yes or no
+    pub fn is_legal(&self, from: Coord, to: Coord, promotion: Option<Piece>) -> bool {
+        self.can_move(from, to, promotion).is_ok()
     }
 
     pub fn try_move(
@@ -260,139 +638,1828 @@ impl Position {
     ) -> Result<MoveInfo, MoveErr> {
         let piece_move = self.can_move(from, to, promotion)?;
 
-        todo!()
+        self.push_history(piece_move);
+        self.apply_move(&piece_move);
+
+        Ok(piece_move)
     }
 
-    fn next_move(&mut self) {
-        let next_player = match self.to_play {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+    /// Whether playing `piece_move`, which must come from `legal_moves`, would put the
+    /// opponent in check, directly or by discovery
+    pub fn move_gives_check(&self, piece_move: &MoveInfo) -> bool {
+        let mut next = self.clone();
+        next.make_move(piece_move);
 
-        self.to_play = next_player;
+        next.is_in_check()
+    }
 
-        // TODO: Update state
-        let king_coord = self.king_coord(next_player);
+    /// Plays `piece_move` and returns what it takes to reverse it with `unmake_move`.
+    /// Unlike `try_move`, the move isn't validated, so it must come from `legal_moves`,
+    /// and it isn't recorded in the history
+    pub fn make_move(&mut self, piece_move: &MoveInfo) -> UndoToken {
+        let token = UndoToken {
+            entry: self.history_entry(*piece_move),
+        };
+        self.apply_move(piece_move);
 
-        self.checks = self.get_attackers(king_coord, next_player);
+        token
     }
-}
 
-fn can_piece_attack(board: Board, from: Coord, piece: Piece, color: Color, to: Coord) -> bool {
-    match piece {
-        Piece::Pawn => can_pawn_attack(from, to, color),
-        Piece::Knight => can_knight_attack(from, to),
-        Piece::Bishop => can_bishop_attack(board, from, to),
-        Piece::Rook => can_rook_attack(board, from, to),
-        Piece::Queen => can_queen_attack(board, from, to),
-        Piece::King => can_king_attack(from, to),
+    pub fn unmake_move(&mut self, token: UndoToken) {
+        self.revert_move(token.entry);
     }
-}
 
-fn can_pawn_attack(from: Coord, to: Coord, color: Color) -> bool {
-    let target_row = match color {
-        Color::White => from.row + 1,
-        Color::Black => from.row.wrapping_sub(1),
-    };
+    fn apply_move(&mut self, piece_move: &MoveInfo) {
+        let player = self.to_play();
+        let (from, to) = (piece_move.from.0, piece_move.to.0);
+
+        if let MoveKind::Castle(side) = piece_move.kind {
+            let coords = self.castle_coords(player, side);
+            castle_board(&mut self.board, player, coords);
+
+            *self.king_coord_mut(player) = coords.1;
+            *self.castle_rights_mut(player) = CastleRights {
+                king: false,
+                queen: false,
+            };
+            self.en_passant = None;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+
+            self.next_move();
+            return;
+        }
 
-    // A pawn attacks one row forward and one column to the left or right
-    to.row == target_row && to.col.abs_diff(from.col) == 1
-}
+        self.board.move_piece(from, to);
+
+        match piece_move.kind {
+            // En passant captures the pawn beside `from`, not the one on `to`
+            MoveKind::EnPassant => {
+                self.board.set_square(
+                    Coord {
+                        row: from.row,
+                        col: to.col,
+                    },
+                    Square::Empty,
+                );
+            }
+            MoveKind::Promotion { piece, .. } => {
+                self.board.set_square(to, Square::Piece(piece, player));
+            }
+            _ => {}
+        }
 
-fn can_knight_attack(from: Coord, to: Coord) -> bool {
-    // Knights move in "L" shapes
-    from.row.abs_diff(to.row) * from.col.abs_diff(to.col) == 2
-}
+        self.en_passant = if piece_move.kind == MoveKind::DoublePush {
+            Some(Coord {
+                row: (from.row + to.row) / 2,
+                col: from.col,
+            })
+        } else {
+            None
+        };
 
-fn can_bishop_attack(board: Board, from: Coord, to: Coord) -> bool {
-    // Bishops move diagonally
-    let dy = from.row.abs_diff(to.row);
-    let dx = from.col.abs_diff(to.col);
+        // Pawn moves and captures are irreversible, restarting the fifty-move count
+        let is_pawn = piece_move.from.1.piece_kind() == Some(Piece::Pawn);
+        if is_pawn || piece_move.captures().is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
 
-    dy == dx && is_clear_diagonal(board, from, to)
-}
+        match piece_move.from.1 {
+            Square::Piece(Piece::King, _) => {
+                *self.castle_rights_mut(player) = CastleRights {
+                    king: false,
+                    queen: false,
+                };
+                *self.king_coord_mut(player) = to;
+            }
+            Square::Piece(Piece::Rook, _) => self.revoke_rook_right(player, from),
+            _ => {}
+        }
 
-fn can_rook_attack(board: Board, from: Coord, to: Coord) -> bool {
-    // Rooks move vertically or horizontally
-    (from.row == to.row || from.col == to.col) && is_clear_line(board, from, to)
-}
+        // Capturing a rook on its corner takes away the opponent's right to castle with it
+        if piece_move.captures() == Some(Piece::Rook) {
+            self.revoke_rook_right(player.opponent(), to);
+        }
 
-fn can_queen_attack(board: Board, from: Coord, to: Coord) -> bool {
-    // Queens combine the movements of rooks and bishops
-    can_bishop_attack(board, from, to) || can_rook_attack(board, from, to)
-}
+        self.next_move();
+    }
 
-fn can_king_attack(from: Coord, to: Coord) -> bool {
-    // Kings move one square in any direction
-    let dy = from.row.abs_diff(to.row);
-    let dx = from.col.abs_diff(to.col);
+    /// Pieces of the side to move pinned to their king, each paired with its pinner
+    pub fn pinned_pieces(&self) -> Vec<(Coord, Coord)> {
+        let player = self.to_play();
+        let king = self.king_coord(player);
 
-    dy <= 1 && dx <= 1
-}
+        let mut pins = Vec::new();
 
-fn is_clear_line(board: Board, from: Coord, to: Coord) -> bool {
-    if from.row == to.row {
-        // Horizontal movement
-        let (start, end) = if from.col < to.col {
-            (from.col + 1, to.col)
-        } else {
-            (to.col + 1, from.col)
-        };
+        for direction in Direction::COMPASS {
+            let mut pieces = self
+                .board
+                .ray(king, direction)
+                .filter(|(_, square)| !square.is_empty());
 
-        for col in start..end {
-            let coord = Coord { row: from.row, col };
-            if !board.square(coord).unwrap().is_empty() {
-                return false;
+            let Some((pinned, Square::Piece(_, color))) = pieces.next() else {
+                continue;
+            };
+            let Some((pinner, Square::Piece(piece, pinner_color))) = pieces.next() else {
+                continue;
+            };
+
+            if color == player && pinner_color != player && slides_along(piece, direction) {
+                pins.push((pinned, pinner));
             }
         }
-    } else if from.col == to.col {
-        // Vertical movement
-        let (start, end) = if from.row < to.row {
-            (from.row + 1, to.row)
-        } else {
-            (to.row + 1, from.row)
+
+        pins
+    }
+
+    /// Moves that follow each piece's movement rules, including ones that leave the
+    /// king in check. Only `legal_moves` and `can_move` enforce full legality
+    pub fn pseudo_legal_moves(&self) -> Vec<MoveInfo> {
+        let mut moves = Vec::new();
+        let player = self.to_play();
+
+        let last_row = match player {
+            Color::White => 7,
+            Color::Black => 0,
         };
 
-        for row in start..end {
-            let coord = Coord { row, col: from.col };
-            if !board.square(coord).unwrap().is_empty() {
-                return false;
+        for (from, piece, _) in self.board.pieces().filter(|&(_, _, color)| color == player) {
+            for to_row in 0..8 {
+                for to_col in 0..8 {
+                    let to = Coord::make(to_row, to_col);
+
+                    let promotions: &[Option<Piece>] = if piece == Piece::Pawn && to_row == last_row
+                    {
+                        &PROMOTIONS
+                    } else {
+                        &[None]
+                    };
+
+                    for &promotion in promotions {
+                        // Castles are only listed under their canonical destination
+                        if let Ok(piece_move) = self.pseudo_legal_move(from, to, promotion) {
+                            if piece_move.to.0 == to {
+                                moves.push(piece_move);
+                            }
+                        }
+                    }
+                }
             }
         }
-    } else {
-        // Not a line move
-        return false;
+
+        moves
     }
 
-    true
-}
+    /// Number of pseudo-legal moves `color` would have if it were their turn
+    pub fn mobility(&self, color: Color) -> usize {
+        if color == self.to_play {
+            return self.pseudo_legal_moves().len();
+        }
 
-fn is_clear_diagonal(board: Board, from: Coord, to: Coord) -> bool {
-    // Check if the movement is diagonal
-    let dy = from.row.abs_diff(to.row);
-    let dx = from.col.abs_diff(to.col);
-    if dy != dx {
-        return false; // Not a diagonal move
+        // Skip cloning the history, which move generation doesn't look at
+        let position = Position {
+            board: self.board,
+            to_play: color,
+            castle_rights: self.castle_rights,
+            castle_layout: self.castle_layout,
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            state: self.state,
+            checks: get_attackers(&self.board, self.king_coord(color), color),
+            king_coord: self.king_coord,
+            history: vec![],
+        };
+
+        position.pseudo_legal_moves().len()
     }
 
-    // Determine the direction of movement
-    let row_step = if to.row > from.row { 1 } else { -1 };
-    let col_step = if to.col > from.col { 1 } else { -1 };
+    pub fn legal_moves(&self) -> Vec<MoveInfo> {
+        let player = self.to_play();
+        let king = self.king_coord(player);
+        let pins = self.pinned_pieces();
+
+        // Out of check, anything but a king move has to capture or block the checker
+        let evades_check = |to: Coord| match self.checks[..] {
+            [] => true,
+            [checker] => match self.board[checker] {
+                Square::Piece(Piece::Bishop | Piece::Rook | Piece::Queen, _) => {
+                    is_on_segment(king, checker, to)
+                }
+                // A checking pawn that just double-pushed can also be taken en passant
+                Square::Piece(Piece::Pawn, _) => {
+                    to == checker
+                        || self
+                            .en_passant
+                            .is_some_and(|ep| ep == to && ep.col == checker.col)
+                }
+                _ => to == checker,
+            },
+            // Only the king can escape a double check
+            _ => false,
+        };
 
-    let mut row = from.row;
-    let mut col = from.col;
+        let mut moves = self.pseudo_legal_moves();
 
-    while row != to.row && col != to.col {
-        row = (row as i8 + row_step) as u8;
-        col = (col as i8 + col_step) as u8;
+        // Cheap geometric tests weed out most illegal moves before the full check test
+        moves.retain(|piece_move| {
+            let (from, from_square) = piece_move.from;
+            let to = piece_move.to.0;
 
-        let coord = Coord { row, col };
-        if let Some(square) = board.square(coord) {
-            if !square.is_empty() {
+            // A pinned piece can only slide along the pin, up to taking the pinner
+            let pinner = pins
+                .iter()
+                .find(|&&(pinned, _)| pinned == from)
+                .map(|&(_, pinner)| pinner);
+            if pinner.is_some_and(|pinner| !is_on_segment(king, pinner, to)) {
+                return false;
+            }
+            if from_square.piece_kind() != Some(Piece::King) && !evades_check(to) {
                 return false;
             }
+
+            self.is_king_safe_after(piece_move)
+        });
+
+        moves
+    }
+
+    pub fn moves_from(&self, from: Coord) -> Vec<Coord> {
+        let mut destinations: Vec<Coord> = self
+            .legal_moves()
+            .iter()
+            .filter(|piece_move| piece_move.from.0 == from)
+            .map(|piece_move| piece_move.to.0)
+            .collect();
+
+        // Promotions produce one move per piece for the same destination
+        destinations.dedup();
+
+        destinations
+    }
+
+    /// Number of legal moves for each of the side to play's pieces, including pieces
+    /// that can't move at all. Each promotion choice counts as its own move
+    pub fn legal_move_count_by_piece(&self) -> Vec<(Coord, usize)> {
+        let moves = self.legal_moves();
+
+        self.board
+            .pieces()
+            .filter(|&(_, _, color)| color == self.to_play)
+            .map(|(coord, _, _)| {
+                let count = moves
+                    .iter()
+                    .filter(|piece_move| piece_move.from.0 == coord)
+                    .count();
+                (coord, count)
+            })
+            .collect()
+    }
+
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_in_place(depth)
+    }
+
+    /// Leaf node count at `depth`, broken down by the kind of the last move played
+    pub fn perft_detailed(&self, depth: u32) -> PerftResult {
+        let mut result = PerftResult::default();
+        self.clone().perft_detailed_in_place(depth, &mut result);
+        result
+    }
+
+    fn perft_detailed_in_place(&mut self, depth: u32, result: &mut PerftResult) {
+        if depth == 0 {
+            result.nodes += 1;
+            return;
+        }
+
+        for piece_move in self.legal_moves() {
+            let token = self.make_move(&piece_move);
+
+            if depth == 1 {
+                match piece_move.kind {
+                    MoveKind::EnPassant => result.en_passants += 1,
+                    MoveKind::Castle(_) => result.castles += 1,
+                    MoveKind::Promotion { .. } => result.promotions += 1,
+                    _ => {}
+                }
+                if piece_move.captures().is_some() {
+                    result.captures += 1;
+                }
+                if self.is_in_check() {
+                    result.checks += 1;
+                }
+                if let State::Checkmate(_) = self.state {
+                    result.checkmates += 1;
+                }
+            }
+
+            self.perft_detailed_in_place(depth - 1, result);
+            self.unmake_move(token);
         }
     }
 
-    true
+    fn perft_in_place(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|piece_move| {
+                let token = self.make_move(piece_move);
+                let nodes = self.perft_in_place(depth - 1);
+                self.unmake_move(token);
+                nodes
+            })
+            .sum()
+    }
+
+    /// Same count as `perft`, with the root moves spread across threads
+    #[cfg(feature = "rayon")]
+    pub fn perft_parallel(&self, depth: u32) -> u64 {
+        use rayon::prelude::*;
+
+        if depth <= 1 {
+            return self.perft(depth);
+        }
+
+        self.legal_moves()
+            .par_iter()
+            .map(|piece_move| {
+                let mut next = self.clone();
+                next.try_move(piece_move.from.0, piece_move.to.0, piece_move.promotion())
+                    .unwrap();
+                next.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    pub fn perft_divide(&self, depth: u32) -> Vec<(String, u64)> {
+        let mut divide: Vec<(String, u64)> = self
+            .legal_moves()
+            .iter()
+            .map(|piece_move| {
+                let mut next = self.clone();
+                next.try_move(piece_move.from.0, piece_move.to.0, piece_move.promotion())
+                    .unwrap();
+                (piece_move.to_uci(), next.perft(depth.saturating_sub(1)))
+            })
+            .collect();
+
+        divide.sort();
+
+        divide
+    }
+
+    /// The same position with the opponent to play, as if the side to move passed.
+    /// Passing out of check would be illegal, so that returns `None`. The null move
+    /// isn't recorded in the history
+    pub fn make_null_move(&self) -> Option<Position> {
+        if self.is_in_check() {
+            return None;
+        }
+
+        let mut next = self.clone();
+        next.en_passant = None;
+        next.next_move();
+
+        Some(next)
+    }
+
+    /// The same position seen from the other side: the board flipped top to bottom with
+    /// every piece changing color, including the side to move. The history isn't kept
+    pub fn mirror_vertical(&self) -> Position {
+        let flip = |coord: Coord| Coord {
+            row: 7 - coord.row,
+            col: coord.col,
+        };
+
+        let mut board = Board::empty();
+        for (coord, piece, color) in self.board.pieces() {
+            board.set_square(flip(coord), Square::Piece(piece, color.opponent()));
+        }
+
+        let (white_rights, black_rights) = self.castle_rights;
+        let (white_king, black_king) = self.king_coord;
+
+        let mut mirrored = Position {
+            board,
+            to_play: self.to_play.opponent(),
+            castle_rights: (black_rights, white_rights),
+            castle_layout: self.castle_layout,
+            en_passant: self.en_passant.map(flip),
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            state: State::Playing,
+            checks: vec![],
+            king_coord: (flip(black_king), flip(white_king)),
+            history: vec![],
+        };
+        mirrored.update_state();
+
+        mirrored
+    }
+
+    pub fn undo(&mut self) -> Option<MoveInfo> {
+        let entry = self.history.pop()?;
+        let piece_move = entry.piece_move;
+
+        self.revert_move(entry);
+
+        Some(piece_move)
+    }
+
+    fn revert_move(&mut self, entry: HistoryEntry) {
+        let piece_move = entry.piece_move;
+
+        let (from, from_square) = piece_move.from;
+        let (to, to_square) = piece_move.to;
+        let player = from_square.player().unwrap();
+
+        if let MoveKind::Castle(side) = piece_move.kind {
+            let (king_from, king_to, rook_from, rook_to) = self.castle_coords(player, side);
+            castle_board(
+                &mut self.board,
+                player,
+                (king_to, king_from, rook_to, rook_from),
+            );
+        } else {
+            // Restoring the original squares also reverts any promotion
+            self.board.set_square(from, from_square);
+            self.board.set_square(to, to_square);
+
+            if piece_move.kind == MoveKind::EnPassant {
+                self.board.set_square(
+                    Coord {
+                        row: from.row,
+                        col: to.col,
+                    },
+                    Square::Piece(Piece::Pawn, player.opponent()),
+                );
+            }
+        }
+
+        if from_square.piece_kind() == Some(Piece::King) {
+            *self.king_coord_mut(player) = from;
+        }
+
+        if player == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        self.to_play = player;
+        self.castle_rights = entry.castle_rights;
+        self.en_passant = entry.en_passant;
+        self.halfmove_clock = entry.halfmove_clock;
+        self.state = entry.state;
+        self.checks = entry.checks;
+    }
+
+    fn push_history(&mut self, piece_move: MoveInfo) {
+        let entry = self.history_entry(piece_move);
+        self.history.push(entry);
+    }
+
+    fn history_entry(&self, piece_move: MoveInfo) -> HistoryEntry {
+        HistoryEntry {
+            piece_move,
+            castle_rights: self.castle_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            state: self.state,
+            checks: self.checks.clone(),
+        }
+    }
+
+    fn revoke_rook_right(&mut self, player: Color, rook_coord: Coord) {
+        let home_row = match player {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+
+        let layout = self.castle_layout;
+        let rights = self.castle_rights_mut(player);
+
+        if rook_coord == Coord::make(home_row, layout.king_rook) {
+            rights.king = false;
+        } else if rook_coord == Coord::make(home_row, layout.queen_rook) {
+            rights.queen = false;
+        }
+    }
+
+    /// King origin, king destination, rook origin and rook destination for castling
+    fn castle_coords(&self, player: Color, side: CastleSide) -> (Coord, Coord, Coord, Coord) {
+        let row = self.king_home(player).row;
+        let layout = self.castle_layout;
+
+        let (king_to, rook_from, rook_to) = match side {
+            CastleSide::King => (6, layout.king_rook, 5),
+            CastleSide::Queen => (2, layout.queen_rook, 3),
+        };
+
+        (
+            Coord::make(row, layout.king),
+            Coord::make(row, king_to),
+            Coord::make(row, rook_from),
+            Coord::make(row, rook_to),
+        )
+    }
+
+    fn castle_move(&self, side: CastleSide) -> MoveInfo {
+        let player = self.to_play();
+        let (king_from, king_to, rook_from, _) = self.castle_coords(player, side);
+
+        // Prefer the familiar two-square king move when it isn't ambiguous
+        let to = if king_from.col.abs_diff(king_to.col) == 2 {
+            king_to
+        } else {
+            rook_from
+        };
+
+        MoveInfo {
+            from: (king_from, Square::Piece(Piece::King, player)),
+            to: (to, self.board[to]),
+            kind: MoveKind::Castle(side),
+        }
+    }
+
+    fn king_home(&self, player: Color) -> Coord {
+        match player {
+            Color::White => Coord::make(0, self.castle_layout.king),
+            Color::Black => Coord::make(7, self.castle_layout.king),
+        }
+    }
+
+    fn next_move(&mut self) {
+        if self.to_play == Color::Black {
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
+
+        let next_player = self.to_play.opponent();
+
+        self.to_play = next_player;
+        self.update_state();
+    }
+
+    fn update_state(&mut self) {
+        let player = self.to_play;
+        let king_coord = self.king_coord(player);
+
+        self.checks = self.get_attackers(king_coord, player);
+
+        self.state = if !self.legal_moves().is_empty() {
+            State::Playing
+        } else if self.is_in_check() {
+            State::Checkmate(player)
+        } else {
+            State::Stalemate(player)
+        };
+
+        if self.state != State::Playing {
+            return;
+        }
+
+        // Fifty moves by each side without a pawn move or capture
+        if self.halfmove_clock >= 100 {
+            self.state = State::Draw(DrawReason::FiftyMove);
+        } else if self.is_insufficient_material() {
+            self.state = State::Draw(DrawReason::InsufficientMaterial);
+        }
+    }
+}
+
+/// Moves the king and rook for castling, which may swap or overlap squares in Chess960
+fn castle_board(board: &mut Board, player: Color, coords: (Coord, Coord, Coord, Coord)) {
+    let (king_from, king_to, rook_from, rook_to) = coords;
+
+    board.set_square(king_from, Square::Empty);
+    board.set_square(rook_from, Square::Empty);
+    board.set_square(king_to, Square::Piece(Piece::King, player));
+    board.set_square(rook_to, Square::Piece(Piece::Rook, player));
+}
+
+const PROMOTIONS: [Option<Piece>; 4] = [
+    Some(Piece::Queen),
+    Some(Piece::Rook),
+    Some(Piece::Bishop),
+    Some(Piece::Knight),
+];
+
+fn get_attackers(board: &Board, coord: Coord, player: Color) -> Vec<Coord> {
+    let opponent = player.opponent();
+    let square = square_index(coord);
+
+    // Knights and kings attack each other's squares symmetrically
+    let mut attackers = KNIGHT_ATTACKS[square] & board.piece_mask(Piece::Knight, opponent)
+        | KING_ATTACKS[square] & board.piece_mask(Piece::King, opponent);
+
+    // Opponent pawns attack from where our own pawn would capture
+    let captures = match player {
+        Color::White => [Direction::NorthWest, Direction::NorthEast],
+        Color::Black => [Direction::SouthWest, Direction::SouthEast],
+    };
+    for from in captures
+        .into_iter()
+        .filter_map(|direction| coord.offset(direction))
+    {
+        if board[from] == Square::Piece(Piece::Pawn, opponent) {
+            attackers |= 1 << square_index(from);
+        }
+    }
+
+    // Sliders attack if they are the first piece along a matching ray
+    for direction in Direction::COMPASS {
+        let first = board
+            .ray(coord, direction)
+            .find(|(_, square)| !square.is_empty());
+
+        if let Some((from, Square::Piece(piece, color))) = first {
+            if color == opponent && slides_along(piece, direction) {
+                attackers |= 1 << square_index(from);
+            }
+        }
+    }
+
+    mask_coords(attackers).collect()
+}
+
+fn attacked_squares(board: &Board, by: Color) -> u64 {
+    let mut mask = 0;
+
+    for (from, piece, _) in board.pieces().filter(|&(_, _, color)| color == by) {
+        for (i, to) in (0..8)
+            .flat_map(|row| (0..8).map(move |col| Coord { row, col }))
+            .enumerate()
+        {
+            if to != from && can_piece_attack(board, from, piece, by, to) {
+                mask |= 1 << i;
+            }
+        }
+    }
+
+    mask
+}
+
+fn slides_along(piece: Piece, direction: Direction) -> bool {
+    match piece {
+        Piece::Queen => true,
+        Piece::Rook => !direction.is_diagonal(),
+        Piece::Bishop => direction.is_diagonal(),
+        _ => false,
+    }
+}
+
+fn can_piece_reach(from: Coord, piece: Piece, color: Color, to: Coord) -> bool {
+    // Same as `can_piece_attack`, but ignoring any pieces in the way
+    let dy = from.row.abs_diff(to.row);
+    let dx = from.col.abs_diff(to.col);
+
+    match piece {
+        Piece::Bishop => dy == dx,
+        Piece::Rook => dy == 0 || dx == 0,
+        Piece::Queen => dy == dx || dy == 0 || dx == 0,
+        _ => can_piece_attack(&Board::empty(), from, piece, color, to),
+    }
+}
+
+fn can_pawn_push(from: Coord, to: Coord, color: Color) -> bool {
+    let (forward, home_row) = match color {
+        Color::White => (Direction::North, 1),
+        Color::Black => (Direction::South, 6),
+    };
+
+    // A pawn pushes one row forward in the same column,
+    // or two rows forward from its starting rank
+    let single = from.offset(forward);
+    let double = single
+        .filter(|_| from.row == home_row)
+        .and_then(|coord| coord.offset(forward));
+
+    Some(to) == single || Some(to) == double
+}
+
+fn can_piece_attack(board: &Board, from: Coord, piece: Piece, color: Color, to: Coord) -> bool {
+    match piece {
+        Piece::Pawn => can_pawn_attack(from, to, color),
+        Piece::Knight => can_knight_attack(from, to),
+        Piece::Bishop => can_bishop_attack(board, from, to),
+        Piece::Rook => can_rook_attack(board, from, to),
+        Piece::Queen => can_queen_attack(board, from, to),
+        Piece::King => can_king_attack(from, to),
+    }
+}
+
+fn can_pawn_attack(from: Coord, to: Coord, color: Color) -> bool {
+    let captures = match color {
+        Color::White => [Direction::NorthWest, Direction::NorthEast],
+        Color::Black => [Direction::SouthWest, Direction::SouthEast],
+    };
+
+    // A pawn attacks one row forward and one column to the left or right
+    captures
+        .into_iter()
+        .any(|direction| from.offset(direction) == Some(to))
+}
+
+fn can_knight_attack(from: Coord, to: Coord) -> bool {
+    // Knights move in "L" shapes
+    KNIGHT_ATTACKS[square_index(from)] & 1 << square_index(to) != 0
+}
+
+fn can_bishop_attack(board: &Board, from: Coord, to: Coord) -> bool {
+    // Bishops move diagonally
+    let dy = from.row.abs_diff(to.row);
+    let dx = from.col.abs_diff(to.col);
+
+    dy == dx && is_clear_path(board, from, to)
+}
+
+fn can_rook_attack(board: &Board, from: Coord, to: Coord) -> bool {
+    // Rooks move vertically or horizontally
+    (from.row == to.row || from.col == to.col) && is_clear_path(board, from, to)
+}
+
+fn can_queen_attack(board: &Board, from: Coord, to: Coord) -> bool {
+    // Queens combine the movements of rooks and bishops
+    can_bishop_attack(board, from, to) || can_rook_attack(board, from, to)
+}
+
+fn can_king_attack(from: Coord, to: Coord) -> bool {
+    // Kings move one square in any direction
+    KING_ATTACKS[square_index(from)] & 1 << square_index(to) != 0
+}
+
+const KNIGHT_ATTACKS: [u64; 64] = attack_table(Direction::KNIGHT);
+const KING_ATTACKS: [u64; 64] = attack_table(Direction::COMPASS);
+
+/// Squares reachable with a single step in any of `directions`, for each source square
+const fn attack_table(directions: [Direction; 8]) -> [u64; 64] {
+    let mut table = [0; 64];
+
+    let mut square = 0;
+    while square < 64 {
+        let (row, col) = ((square / 8) as i8, (square % 8) as i8);
+
+        let mut i = 0;
+        while i < directions.len() {
+            let (dx, dy) = directions[i].delta();
+            let (to_row, to_col) = (row + dy, col + dx);
+            if to_row >= 0 && to_row < 8 && to_col >= 0 && to_col < 8 {
+                table[square] |= 1 << (to_row * 8 + to_col);
+            }
+            i += 1;
+        }
+
+        square += 1;
+    }
+
+    table
+}
+
+fn square_index(coord: Coord) -> usize {
+    (coord.row * 8 + coord.col) as usize
+}
+
+/// Whether `coord` lies on the line from `from` (exclusive) to `to` (inclusive)
+fn is_on_segment(from: Coord, to: Coord, coord: Coord) -> bool {
+    let Some(direction) = Direction::between(from, to) else {
+        return false;
+    };
+
+    core::iter::successors(from.offset(direction), |&step| {
+        (step != to).then(|| step.offset(direction)).flatten()
+    })
+    .any(|step| step == coord)
+}
+
+fn is_clear_path(board: &Board, from: Coord, to: Coord) -> bool {
+    let Some(direction) = Direction::between(from, to) else {
+        return true;
+    };
+
+    // Only the squares strictly between `from` and `to` need to be empty
+    board
+        .ray(from, direction)
+        .take_while(|&(coord, _)| coord != to)
+        .all(|(_, square)| square.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coord(algebraic: &str) -> Coord {
+        Coord::from_algebraic(algebraic).unwrap()
+    }
+
+    fn fen(fen: &str) -> Position {
+        Position::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn get_attackers_reports_each_attacker_once() {
+        let position = fen("b3r2k/8/5n2/5p2/4K3/8/8/8 w - - 0 1");
+
+        let mut attackers = position.get_attackers(coord("e4"), Color::White);
+        attackers.sort();
+
+        let mut expected = vec![coord("a8"), coord("e8"), coord("f6"), coord("f5")];
+        expected.sort();
+
+        assert_eq!(attackers, expected);
+    }
+
+    #[test]
+    fn try_move_pushes_a_pawn() {
+        let mut position = Position::standard();
+
+        let piece_move = position.try_move(coord("e2"), coord("e3"), None).unwrap();
+
+        assert_eq!(piece_move.kind(), MoveKind::Quiet);
+        assert_eq!(position.board()[coord("e2")], Square::Empty);
+        assert_eq!(
+            position.board()[coord("e3")],
+            Square::Piece(Piece::Pawn, Color::White)
+        );
+        assert_eq!(position.to_play(), Color::Black);
+    }
+
+    #[test]
+    fn try_move_captures() {
+        let mut position = fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+
+        let piece_move = position.try_move(coord("e4"), coord("d5"), None).unwrap();
+
+        assert_eq!(piece_move.kind(), MoveKind::Capture(Piece::Pawn));
+        assert_eq!(position.board()[coord("e4")], Square::Empty);
+        assert_eq!(
+            position.board()[coord("d5")],
+            Square::Piece(Piece::Pawn, Color::White)
+        );
+        assert_eq!(position.board().count(Piece::Pawn, Color::Black), 0);
+    }
+
+    #[test]
+    fn try_move_king_clears_both_castling_rights() {
+        let mut position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        position.try_move(coord("e1"), coord("f1"), None).unwrap();
+
+        let none = CastleRights {
+            king: false,
+            queen: false,
+        };
+        assert_eq!(position.castle_rights(Color::White), none);
+        assert_eq!(position.king_coord(Color::White), coord("f1"));
+        assert_eq!(
+            position.castle_rights(Color::Black),
+            CastleRights {
+                king: true,
+                queen: true,
+            }
+        );
+    }
+
+    #[test]
+    fn try_move_rejects_illegal_moves_without_changes() {
+        let mut position = Position::standard();
+
+        assert_eq!(
+            position.try_move(coord("e2"), coord("e5"), None),
+            Err(MoveErr::InvalidMove)
+        );
+        assert_eq!(position, Position::standard());
+    }
+
+    #[test]
+    fn en_passant_captures_the_pawn_that_double_pushed() {
+        let mut position = fen("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        position.try_move(coord("e2"), coord("e4"), None).unwrap();
+        assert_eq!(position.en_passant(), Some(coord("e3")));
+
+        // Black lets the chance pass, so the target square is cleared
+        position.try_move(coord("g8"), coord("f6"), None).unwrap();
+        assert_eq!(position.en_passant(), None);
+
+        position.try_move(coord("c2"), coord("c4"), None).unwrap();
+        assert_eq!(position.en_passant(), Some(coord("c3")));
+
+        let piece_move = position.try_move(coord("d4"), coord("c3"), None).unwrap();
+
+        assert_eq!(piece_move.kind(), MoveKind::EnPassant);
+        assert_eq!(position.board()[coord("c4")], Square::Empty);
+        assert_eq!(
+            position.board()[coord("c3")],
+            Square::Piece(Piece::Pawn, Color::Black)
+        );
+        assert_eq!(position.en_passant(), None);
+    }
+
+    #[test]
+    fn blocked_pawns_cannot_push() {
+        let position = fen("4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1");
+        assert_eq!(
+            position.can_move(coord("e2"), coord("e3"), None),
+            Err(MoveErr::PathBlocked)
+        );
+        assert_eq!(
+            position.can_move(coord("e2"), coord("e4"), None),
+            Err(MoveErr::PathBlocked)
+        );
+
+        let position = fen("4k3/8/8/8/4n3/8/4P3/4K3 w - - 0 1");
+        assert!(position.can_move(coord("e2"), coord("e3"), None).is_ok());
+        assert_eq!(
+            position.can_move(coord("e2"), coord("e4"), None),
+            Err(MoveErr::PathBlocked)
+        );
+    }
+
+    #[test]
+    fn pawns_off_their_home_rank_step_once() {
+        let position = fen("4k3/8/8/8/8/4P3/8/4K3 w - - 0 1");
+
+        assert!(position.can_move(coord("e3"), coord("e4"), None).is_ok());
+        assert_eq!(
+            position.can_move(coord("e3"), coord("e5"), None),
+            Err(MoveErr::InvalidMove)
+        );
+    }
+
+    #[test]
+    fn double_push_arms_en_passant() {
+        let mut position = Position::standard();
+
+        let piece_move = position.try_move(coord("d2"), coord("d4"), None).unwrap();
+
+        assert_eq!(piece_move.kind(), MoveKind::DoublePush);
+        assert_eq!(position.en_passant(), Some(coord("d3")));
+    }
+
+    #[test]
+    fn start_position_has_twenty_legal_moves() {
+        assert_eq!(Position::standard().legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn castles_are_listed_as_their_own_moves() {
+        let position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        let castles: Vec<_> = position
+            .legal_moves()
+            .iter()
+            .filter_map(MoveInfo::castle)
+            .collect();
+
+        assert_eq!(castles.len(), 2);
+        assert!(castles.contains(&CastleSide::King));
+        assert!(castles.contains(&CastleSide::Queen));
+    }
+
+    #[test]
+    fn promotions_are_listed_for_each_piece() {
+        let position = fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+
+        let mut promotions: Vec<_> = position
+            .legal_moves()
+            .iter()
+            .filter_map(MoveInfo::promotion)
+            .collect();
+        promotions.sort();
+
+        assert_eq!(
+            promotions,
+            [Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen]
+        );
+    }
+
+    #[test]
+    fn pinned_knight_has_no_moves() {
+        let position = fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert!(position.moves_from(coord("e2")).is_empty());
+
+        let position = fen("4k3/3r4/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert_eq!(position.moves_from(coord("e2")).len(), 6);
+    }
+
+    #[test]
+    fn moves_from_ignores_other_squares() {
+        let position = Position::standard();
+
+        assert!(position.moves_from(coord("e4")).is_empty());
+        assert!(position.moves_from(coord("e7")).is_empty());
+        assert_eq!(position.moves_from(coord("g1")), [coord("f3"), coord("h3")]);
+    }
+
+    #[test]
+    fn perft_from_the_start_position() {
+        let position = Position::standard();
+
+        assert_eq!(position.perft(1), 20);
+        assert_eq!(position.perft(2), 400);
+        assert_eq!(position.perft(3), 8902);
+        assert_eq!(position.perft(4), 197281);
+    }
+
+    #[test]
+    fn perft_divide_of_the_start_position() {
+        let divide = Position::standard().perft_divide(2);
+
+        assert_eq!(divide.len(), 20);
+        assert!(divide.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(divide.iter().all(|(_, nodes)| *nodes == 20));
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 400);
+        assert_eq!(divide[0], ("a2a3".to_string(), 20));
+    }
+
+    #[test]
+    fn back_rank_mate_ends_the_game() {
+        let mut position = fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+
+        position.try_move(coord("a1"), coord("a8"), None).unwrap();
+
+        assert_eq!(position.state(), State::Checkmate(Color::Black));
+    }
+
+    #[test]
+    fn stalemate_ends_the_game() {
+        let mut position = fen("k7/8/8/2Q5/8/8/8/K7 w - - 0 1");
+
+        position.try_move(coord("c5"), coord("c7"), None).unwrap();
+
+        assert_eq!(position.state(), State::Stalemate(Color::Black));
+        assert!(!position.is_in_check());
+    }
+
+    #[test]
+    fn shuffling_knights_reaches_the_fifty_move_draw() {
+        let mut position = Position::standard();
+        let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+
+        for ply in 0..100 {
+            assert_eq!(position.state(), State::Playing);
+
+            let (from, to) = shuffle[ply % 4];
+            position.try_move(coord(from), coord(to), None).unwrap();
+        }
+
+        assert_eq!(position.halfmove_clock(), 100);
+        assert_eq!(position.state(), State::Draw(DrawReason::FiftyMove));
+    }
+
+    #[test]
+    fn pawn_moves_reset_the_halfmove_clock() {
+        let mut position = Position::standard();
+
+        position.try_move(coord("g1"), coord("f3"), None).unwrap();
+        position.try_move(coord("g8"), coord("f6"), None).unwrap();
+        assert_eq!(position.halfmove_clock(), 2);
+
+        position.try_move(coord("e2"), coord("e4"), None).unwrap();
+        assert_eq!(position.halfmove_clock(), 0);
+    }
+
+    #[test]
+    fn fullmove_number_advances_after_black_moves() {
+        let mut position = Position::standard();
+        assert_eq!(position.fullmove_number(), 1);
+
+        position.try_move(coord("e2"), coord("e4"), None).unwrap();
+        assert_eq!(position.fullmove_number(), 1);
+
+        position.try_move(coord("e7"), coord("e5"), None).unwrap();
+        assert_eq!(position.fullmove_number(), 2);
+
+        position.try_move(coord("g1"), coord("f3"), None).unwrap();
+        assert_eq!(position.fullmove_number(), 2);
+    }
+
+    #[test]
+    fn dead_positions_are_insufficient_material() {
+        let fens = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1",
+        ];
+
+        for position in fens.map(fen) {
+            assert!(position.is_insufficient_material());
+            assert_eq!(
+                position.state(),
+                State::Draw(DrawReason::InsufficientMaterial)
+            );
+        }
+    }
+
+    #[test]
+    fn a_pawn_is_sufficient_material() {
+        let position = fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        assert!(!position.is_insufficient_material());
+        assert_eq!(position.state(), State::Playing);
+    }
+
+    #[test]
+    fn opposite_colored_bishops_are_sufficient_material() {
+        let position = fen("3bk3/8/8/8/8/8/8/3BK3 w - - 0 1");
+
+        assert!(!position.is_insufficient_material());
+    }
+
+    #[test]
+    fn draws_report_their_reason() {
+        let fifty_moves = fen("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80");
+        assert_eq!(fifty_moves.state(), State::Draw(DrawReason::FiftyMove));
+
+        let bare_kings = fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(
+            bare_kings.state(),
+            State::Draw(DrawReason::InsufficientMaterial)
+        );
+
+        // Stalemate stays a state of its own rather than a draw reason
+        let stalemate = fen("k7/2Q5/8/8/8/8/8/K7 b - - 0 1");
+        assert_eq!(stalemate.state(), State::Stalemate(Color::Black));
+    }
+
+    #[test]
+    fn capturing_a_corner_rook_revokes_its_castling_right() {
+        let mut position = fen("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1");
+
+        position.try_move(coord("g2"), coord("h1"), None).unwrap();
+
+        assert!(!position.castle_rights(Color::White).king);
+        assert!(position.castle_rights(Color::White).queen);
+        assert_eq!(
+            position.try_castle(CastleSide::King),
+            Err(MoveErr::NoCastlingRight)
+        );
+    }
+
+    #[test]
+    fn moving_a_rook_out_and_back_revokes_its_castling_right() {
+        let mut position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        for (from, to) in [("h1", "h2"), ("a8", "b8"), ("h2", "h1"), ("b8", "a8")] {
+            position.try_move(coord(from), coord(to), None).unwrap();
+        }
+
+        assert_eq!(
+            position.try_move(coord("e1"), coord("g1"), None),
+            Err(MoveErr::NoCastlingRight)
+        );
+        assert!(position.castle_rights(Color::White).queen);
+        assert!(!position.castle_rights(Color::Black).queen);
+        assert!(position.castle_rights(Color::Black).king);
+    }
+
+    #[test]
+    fn moving_the_king_out_and_back_revokes_both_castling_rights() {
+        let mut position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        for (from, to) in [("e1", "f1"), ("a8", "b8"), ("f1", "e1"), ("b8", "a8")] {
+            position.try_move(coord(from), coord(to), None).unwrap();
+        }
+
+        assert_eq!(
+            position.try_move(coord("e1"), coord("g1"), None),
+            Err(MoveErr::NoCastlingRight)
+        );
+        assert_eq!(
+            position.try_move(coord("e1"), coord("c1"), None),
+            Err(MoveErr::NoCastlingRight)
+        );
+    }
+
+    #[test]
+    fn undo_reverts_a_capturing_promotion() {
+        let original = fen("r3k3/1P6/8/8/8/8/8/4K3 w q - 0 1");
+        let mut position = original.clone();
+
+        let piece_move = position
+            .try_move(coord("b7"), coord("a8"), Some(Piece::Queen))
+            .unwrap();
+        assert_eq!(
+            piece_move.kind(),
+            MoveKind::Promotion {
+                piece: Piece::Queen,
+                captures: Some(Piece::Rook),
+            }
+        );
+        assert!(!position.castle_rights(Color::Black).queen);
+
+        assert_eq!(position.undo(), Some(piece_move));
+        assert_eq!(position, original);
+        assert_eq!(position.undo(), None);
+    }
+
+    #[test]
+    fn undo_reverts_castling_and_en_passant() {
+        let original = fen("r3k2r/8/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1");
+        let mut position = original.clone();
+
+        for (from, to) in [("e2", "e4"), ("d4", "e3"), ("e1", "g1")] {
+            position.try_move(coord(from), coord(to), None).unwrap();
+        }
+        while position.undo().is_some() {}
+
+        assert_eq!(position, original);
+    }
+
+    #[test]
+    fn castling_needs_the_king_on_its_layout_file() {
+        let mut position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        position.castle_layout.king = 3;
+
+        assert_eq!(
+            position.castle_status(Color::White, CastleSide::Queen),
+            Err(MoveErr::NoCastlingRight)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_json_round_trip() {
+        let mut position = Position::default();
+        position.try_move(coord("e2"), coord("e4"), None).unwrap();
+
+        for position in [Position::default(), position] {
+            let json = serde_json::to_string(&position).unwrap();
+            assert_eq!(serde_json::from_str::<Position>(&json).unwrap(), position);
+        }
+    }
+
+    #[test]
+    fn move_errors_display() {
+        let messages = [
+            (
+                MoveErr::PieceNotOwned,
+                "no piece of the side to move on that square",
+            ),
+            (MoveErr::DestinationOccupied, "destination occupied"),
+            (MoveErr::KingInCheck, "king would be in check"),
+            (MoveErr::NoCastlingRight, "castling right has been lost"),
+            (MoveErr::PathBlocked, "path is blocked"),
+            (MoveErr::InvalidMove, "piece cannot move that way"),
+            (MoveErr::InvalidPromotion, "invalid promotion"),
+            (MoveErr::OutOfBounds, "square is off the board"),
+            (MoveErr::GameOver, "the game is already over"),
+        ];
+
+        for (err, message) in messages {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn move_errors_box_as_errors() {
+        fn play(position: &mut Position) -> Result<(), Box<dyn core::error::Error>> {
+            position.try_move(coord("e2"), coord("e5"), None)?;
+            Ok(())
+        }
+
+        let err = play(&mut Position::default()).unwrap_err();
+        assert_eq!(err.to_string(), "piece cannot move that way");
+    }
+
+    #[test]
+    fn attacked_squares_matches_per_square_checks() {
+        let position = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        for by in [Color::White, Color::Black] {
+            let mask = position.attacked_squares(by);
+
+            for row in 0..8 {
+                for col in 0..8 {
+                    let coord = Coord::make(row, col);
+                    assert_eq!(
+                        mask & 1 << (row * 8 + col) != 0,
+                        position.is_square_attacked(coord, by.opponent()),
+                        "{coord} attacked by {by:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pinned_pieces_name_their_pinners() {
+        let position = fen("4k3/4r3/8/b7/8/8/3BN3/4K3 w - - 0 1");
+
+        let mut pins = position.pinned_pieces();
+        pins.sort();
+        assert_eq!(
+            pins,
+            [(coord("d2"), coord("a5")), (coord("e2"), coord("e7"))]
+        );
+
+        // The knight can't stay on the file, while the bishop can still slide along the pin
+        assert!(position
+            .legal_moves()
+            .iter()
+            .all(|piece_move| piece_move.from().0 != coord("e2")));
+        assert_eq!(
+            position.moves_from(coord("d2")),
+            [coord("c3"), coord("b4"), coord("a5")]
+        );
+    }
+
+    #[test]
+    fn kings_cannot_stand_side_by_side() {
+        let mut position = fen("8/8/8/3k4/8/3K4/8/8 w - - 0 1");
+
+        for to in ["c4", "d4", "e4"] {
+            assert_eq!(
+                position.try_move(coord("d3"), coord(to), None),
+                Err(MoveErr::KingInCheck),
+                "{to}"
+            );
+        }
+        assert_eq!(position.moves_from(coord("d3")).len(), 5);
+    }
+
+    #[test]
+    fn evasions_block_or_capture_a_single_check() {
+        // The rook on e8 checks along the file, and the bishop can block on e2 or e3
+        // while the knight can take it
+        let position = fen("4r1k1/8/8/8/8/5N2/3B4/4K3 w - - 0 1");
+        let mut evasions: Vec<String> = position
+            .legal_moves()
+            .iter()
+            .filter(|piece_move| piece_move.from().1.piece_kind() != Some(Piece::King))
+            .map(|piece_move| piece_move.to_uci())
+            .collect();
+        evasions.sort();
+
+        assert_eq!(evasions, ["d2e3", "f3e5"]);
+        assert!(position.legal_moves().iter().all(|piece_move| {
+            let mut after = position.clone();
+            after.make_move(piece_move);
+            !after.is_square_attacked(after.king_coord(Color::White), Color::White)
+        }));
+    }
+
+    #[test]
+    fn double_check_leaves_only_king_moves() {
+        // Either checker could be captured on its own, but not both at once
+        let position = fen("4k3/8/8/8/1b6/8/5N2/1R2K2r w - - 0 1");
+        assert_eq!(position.checkers().len(), 2);
+
+        let moves = position.legal_moves();
+        assert!(!moves.is_empty());
+        assert!(moves
+            .iter()
+            .all(|piece_move| piece_move.from().1.piece_kind() == Some(Piece::King)));
+    }
+
+    #[test]
+    fn attack_tables_match_the_geometry() {
+        for from in (0..64).map(|i| Coord::make(i / 8, i % 8)) {
+            for to in (0..64).map(|i| Coord::make(i / 8, i % 8)) {
+                let (rows, cols) = (from.row.abs_diff(to.row), from.col.abs_diff(to.col));
+
+                assert_eq!(
+                    can_knight_attack(from, to),
+                    matches!((rows, cols), (1, 2) | (2, 1)),
+                    "knight {from} to {to}"
+                );
+                assert_eq!(
+                    can_king_attack(from, to),
+                    rows.max(cols) == 1,
+                    "king {from} to {to}"
+                );
+            }
+        }
+
+        assert_eq!(KNIGHT_ATTACKS[0].count_ones(), 2);
+        assert_eq!(KING_ATTACKS[square_index(coord("e4"))].count_ones(), 8);
+    }
+
+    #[test]
+    fn bitboards_stay_in_sync_with_the_mailbox() {
+        fn walk(position: &mut Position, depth: u32) -> u64 {
+            let board = position.board();
+            assert_eq!(Board::from_bytes(board.to_bytes()).as_ref(), Some(board));
+
+            if depth == 0 {
+                return 1;
+            }
+
+            let mut nodes = 0;
+            for piece_move in position.legal_moves() {
+                let token = position.make_move(&piece_move);
+                nodes += walk(position, depth - 1);
+                position.unmake_move(token);
+            }
+            nodes
+        }
+
+        // Kiwipete covers castling, en passant and promotions within two plies
+        let mut position =
+            fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(walk(&mut position, 2), 2039);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn parallel_perft_matches_serial() {
+        let positions = [
+            Position::standard(),
+            fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+        ];
+
+        for position in positions {
+            for depth in 0..=2 {
+                assert_eq!(position.perft_parallel(depth), position.perft(depth));
+            }
+        }
+        assert_eq!(Position::standard().perft_parallel(3), 8902);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    #[ignore = "takes minutes unoptimised, CI runs it with --release"]
+    fn parallel_perft_5_from_the_start_position() {
+        let position = Position::standard();
+
+        assert_eq!(position.perft_parallel(5), 4_865_609);
+        assert_eq!(position.perft(5), 4_865_609);
+    }
+
+    #[test]
+    fn pseudo_legal_moves_include_moves_off_a_pin() {
+        let position = fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        let pseudo_legal = position.pseudo_legal_moves();
+        let legal = position.legal_moves();
+
+        // All six of the pinned knight's jumps would expose the king
+        assert_eq!(pseudo_legal.len() - legal.len(), 6);
+        assert!(legal
+            .iter()
+            .all(|piece_move| pseudo_legal.contains(piece_move)));
+    }
+
+    #[test]
+    fn generated_moves_carry_their_kind() {
+        let position = fen("r3k2r/1P6/8/3pP3/8/8/7P/R3K2R w KQkq d6 0 1");
+        let kind = |uci: &str| {
+            position
+                .legal_moves()
+                .into_iter()
+                .find(|piece_move| piece_move.to_uci() == uci)
+                .map(|piece_move| piece_move.kind())
+        };
+
+        assert_eq!(kind("h2h3"), Some(MoveKind::Quiet));
+        assert_eq!(kind("h2h4"), Some(MoveKind::DoublePush));
+        assert_eq!(kind("a1a8"), Some(MoveKind::Capture(Piece::Rook)));
+        assert_eq!(kind("e5d6"), Some(MoveKind::EnPassant));
+        assert_eq!(kind("e1g1"), Some(MoveKind::Castle(CastleSide::King)));
+        assert_eq!(kind("e1c1"), Some(MoveKind::Castle(CastleSide::Queen)));
+        assert_eq!(
+            kind("b7b8n"),
+            Some(MoveKind::Promotion {
+                piece: Piece::Knight,
+                captures: None
+            })
+        );
+        assert_eq!(
+            kind("b7a8q"),
+            Some(MoveKind::Promotion {
+                piece: Piece::Queen,
+                captures: Some(Piece::Rook)
+            })
+        );
+    }
+
+    #[test]
+    fn try_move_reports_the_kind_it_played() {
+        let mut position = fen("r3k2r/1P6/8/3pP3/8/8/7P/R3K2R w KQkq d6 0 1");
+
+        let played = position.try_move(coord("e5"), coord("d6"), None).unwrap();
+        assert_eq!(played.kind(), MoveKind::EnPassant);
+        assert_eq!(played.captures(), Some(Piece::Pawn));
+    }
+
+    #[test]
+    fn checkmate_and_stalemate_predicates() {
+        let mate = fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert!(mate.is_checkmate());
+        assert!(!mate.is_stalemate());
+
+        let stalemate = fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(stalemate.is_stalemate());
+        assert!(!stalemate.is_checkmate());
+
+        let start = Position::standard();
+        assert!(!start.is_checkmate());
+        assert!(!start.is_stalemate());
+    }
+
+    #[test]
+    fn null_move_passes_the_turn() {
+        let mut position = Position::standard();
+        position.try_move(coord("e2"), coord("e4"), None).unwrap();
+        assert_eq!(position.en_passant(), Some(coord("e3")));
+
+        let passed = position.make_null_move().unwrap();
+        assert_eq!(passed.to_play(), Color::White);
+        assert_eq!(passed.en_passant(), None);
+        assert_eq!(passed.board(), position.board());
+    }
+
+    #[test]
+    fn null_move_is_refused_in_check() {
+        let position = fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+        assert_eq!(position.make_null_move(), None);
+    }
+
+    #[test]
+    fn unmake_restores_the_position_exactly() {
+        let positions = [
+            Position::standard(),
+            fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            fen("r3k2r/1P6/8/3pP3/8/8/7P/R3K2R w KQkq d6 0 1"),
+            fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+        ];
+
+        for original in positions {
+            let mut position = original.clone();
+
+            for piece_move in original.legal_moves() {
+                let token = position.make_move(&piece_move);
+                assert_ne!(position, original);
+                position.unmake_move(token);
+
+                assert_eq!(position, original, "{}", piece_move.to_uci());
+                assert_eq!(position.zobrist(), original.zobrist());
+            }
+        }
+    }
+
+    #[test]
+    fn perft_detailed_matches_the_reference_breakdowns() {
+        assert_eq!(
+            Position::standard().perft_detailed(3),
+            PerftResult {
+                nodes: 8902,
+                captures: 34,
+                en_passants: 0,
+                castles: 0,
+                promotions: 0,
+                checks: 12,
+                checkmates: 0,
+            }
+        );
+
+        let kiwipete = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(
+            kiwipete.perft_detailed(2),
+            PerftResult {
+                nodes: 2039,
+                captures: 351,
+                en_passants: 1,
+                castles: 91,
+                promotions: 0,
+                checks: 3,
+                checkmates: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn open_positions_have_more_mobility() {
+        let open = fen("4k3/8/8/8/3Q4/8/8/4K3 w - - 0 1");
+        let cramped = fen("4k3/8/8/8/8/8/PPP5/QRK5 w - - 0 1");
+
+        assert!(open.mobility(Color::White) > cramped.mobility(Color::White));
+
+        // Counted the same whether or not it's that side's turn
+        let waiting = fen("4k3/8/8/8/3Q4/8/8/4K3 b - - 0 1");
+        assert_eq!(waiting.mobility(Color::White), open.mobility(Color::White));
+    }
+
+    #[test]
+    fn empty_position_is_a_harmless_stalemate() {
+        let position = Position::empty();
+
+        assert_eq!(position.board().pieces().count(), 0);
+        assert!(position.legal_moves().is_empty());
+        assert_eq!(position.state(), State::Stalemate(Color::White));
+        assert_eq!(position.king_coord(Color::White), coord("e1"));
+        assert_eq!(position.to_fen(), "8/8/8/8/8/8/8/8 w - - 0 1");
+    }
+
+    #[test]
+    fn pawns_on_their_last_rank_attack_nothing() {
+        let mut board = Board::empty();
+        board.set_square(coord("d1"), Square::Piece(Piece::Pawn, Color::Black));
+        board.set_square(coord("e8"), Square::Piece(Piece::Pawn, Color::White));
+
+        assert_eq!(attacked_squares(&board, Color::Black), 0);
+        assert_eq!(attacked_squares(&board, Color::White), 0);
+
+        for i in 0..64 {
+            let to = Coord::make(i / 8, i % 8);
+            assert!(!can_pawn_attack(coord("d1"), to, Color::Black), "{to}");
+            assert!(!can_pawn_attack(coord("e8"), to, Color::White), "{to}");
+            for player in [Color::White, Color::Black] {
+                assert!(get_attackers(&board, to, player).is_empty(), "{to}");
+            }
+        }
+    }
+
+    #[test]
+    fn checkers_of_a_rook_check() {
+        let position = fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1");
+
+        assert_eq!(position.checkers(), [coord("e1")]);
+        assert!(!position.is_double_check());
+        assert!(Position::standard().checkers().is_empty());
+    }
+
+    #[test]
+    fn checkers_of_a_discovered_double_check() {
+        let mut position = fen("4k3/8/8/8/4B3/8/8/4R1K1 w - - 0 1");
+        position.try_move(coord("e4"), coord("c6"), None).unwrap();
+
+        let mut checkers = position.checkers().to_vec();
+        checkers.sort();
+        assert_eq!(checkers, [coord("e1"), coord("c6")]);
+        assert!(position.is_double_check());
+    }
+
+    #[test]
+    fn move_counts_by_piece_in_the_start_position() {
+        let counts = Position::standard().legal_move_count_by_piece();
+        assert_eq!(counts.len(), 16);
+
+        for (coord, count) in counts {
+            let expected = match Position::standard().board()[coord].piece_kind() {
+                Some(Piece::Knight | Piece::Pawn) => 2,
+                _ => 0,
+            };
+            assert_eq!(count, expected, "{coord}");
+        }
+    }
+
+    #[test]
+    fn move_counts_by_piece_count_each_promotion() {
+        let position = fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let counts = position.legal_move_count_by_piece();
+
+        assert!(counts.contains(&(coord("b7"), 4)));
+        assert!(counts.contains(&(coord("e1"), 5)));
+    }
+
+    #[test]
+    fn is_legal_agrees_with_can_move() {
+        let position = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let before = position.clone();
+        let moves = [
+            ("e1", "g1", None),
+            ("e1", "c1", None),
+            ("d5", "e6", None),
+            ("e5", "f7", None),
+            ("a2", "a4", None),
+            ("a2", "a5", None),
+            ("e1", "e2", None),
+            ("d2", "h6", None),
+            ("d2", "a5", None),
+            ("b4", "b3", None),
+            ("e4", "e5", None),
+            ("a1", "a1", None),
+            ("d5", "d6", Some(Piece::Queen)),
+        ];
+
+        for (from, to, promotion) in moves {
+            let (from, to) = (coord(from), coord(to));
+            assert_eq!(
+                position.is_legal(from, to, promotion),
+                position.can_move(from, to, promotion).is_ok(),
+                "{from}{to}"
+            );
+        }
+        assert_eq!(position, before);
+    }
+
+    #[test]
+    fn same_shade_bishop_pair_cannot_mate() {
+        // c1 and e3 are both dark squares
+        let position = fen("4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1");
+
+        assert!(position.is_insufficient_material());
+        assert_eq!(
+            position.state(),
+            State::Draw(DrawReason::InsufficientMaterial)
+        );
+    }
+
+    #[test]
+    fn bishop_pair_on_both_shades_can_mate() {
+        let position = fen("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1");
+
+        assert!(!position.is_insufficient_material());
+        assert_eq!(position.state(), State::Playing);
+    }
+
+    fn legal_move(position: &Position, from: &str, to: &str) -> MoveInfo {
+        position.can_move(coord(from), coord(to), None).unwrap()
+    }
+
+    #[test]
+    fn rook_checks_along_a_file() {
+        let position = fen("4k3/8/8/8/8/8/8/R5K1 w - - 0 1");
+
+        assert!(position.move_gives_check(&legal_move(&position, "a1", "e1")));
+        assert!(!position.move_gives_check(&legal_move(&position, "a1", "d1")));
+        assert!(!position.is_in_check());
+    }
+
+    #[test]
+    fn moving_a_blocker_discovers_check() {
+        // The knight on e4 shields the black king from the rook on e1
+        let position = fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1");
+
+        assert!(position.move_gives_check(&legal_move(&position, "e4", "c3")));
+        assert!(position.move_gives_check(&legal_move(&position, "e4", "d6")));
+        assert!(!position.move_gives_check(&legal_move(&position, "g1", "h1")));
+    }
+
+    #[test]
+    fn en_passant_cannot_expose_the_king_along_the_rank() {
+        // Taking on d6 would clear both pawns off the fifth rank, leaving the white king
+        // open to the rook on h5
+        let position = fen("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1");
+
+        assert_eq!(
+            position.can_move(coord("e5"), coord("d6"), None),
+            Err(MoveErr::KingInCheck)
+        );
+        assert!(!position.is_legal(coord("e5"), coord("d6"), None));
+        assert!(!position
+            .legal_moves()
+            .iter()
+            .any(|piece_move| piece_move.kind() == MoveKind::EnPassant));
+        assert!(position.is_legal(coord("e5"), coord("e6"), None));
+
+        // With the rook off the rank the capture is fine
+        let position = fen("8/8/8/K2pP3/7r/8/8/4k3 w - d6 0 1");
+        assert_eq!(
+            position
+                .can_move(coord("e5"), coord("d6"), None)
+                .map(|piece_move| piece_move.kind()),
+            Ok(MoveKind::EnPassant)
+        );
+    }
+
+    #[test]
+    fn castle_status_for_the_side_not_to_move() {
+        let open = fen("r3k2r/8/8/8/8/8/8/4K3 w kq - 0 1");
+        assert_eq!(open.castle_status(Color::Black, CastleSide::Queen), Ok(()));
+        assert_eq!(open.castle_status(Color::Black, CastleSide::King), Ok(()));
+        assert_eq!(
+            open.castle_status(Color::White, CastleSide::Queen),
+            Err(MoveErr::NoCastlingRight)
+        );
+
+        let blocked = fen("r1n1k2r/8/8/8/8/8/8/4K3 w kq - 0 1");
+        assert_eq!(
+            blocked.castle_status(Color::Black, CastleSide::Queen),
+            Err(MoveErr::PathBlocked)
+        );
+
+        // The white rook covers d8, which the black king would have to cross
+        let attacked = fen("r3k2r/8/8/8/8/8/8/3RK3 w kq - 0 1");
+        assert_eq!(
+            attacked.castle_status(Color::Black, CastleSide::Queen),
+            Err(MoveErr::KingInCheck)
+        );
+        assert_eq!(
+            attacked.castle_status(Color::Black, CastleSide::King),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn move_counters_saturate_instead_of_overflowing() {
+        let mut position = fen("4k3/8/8/8/8/8/8/R3K3 b - - 65535 4294967295");
+
+        position.try_move(coord("e8"), coord("d8"), None).unwrap();
+        assert_eq!(position.halfmove_clock(), u16::MAX);
+        assert_eq!(position.fullmove_number(), u32::MAX);
+
+        let passed = position.make_null_move().unwrap();
+        assert_eq!(passed.halfmove_clock(), u16::MAX);
+    }
 }
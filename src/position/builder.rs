@@ -0,0 +1,384 @@
+use alloc::vec;
+use core::fmt;
+
+use crate::board::{mask_coords, Board, Color, Coord, Piece, Square};
+
+use super::{CastleRights, Position, State, STANDARD_LAYOUT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SetupError {
+    MissingKing(Color),
+    TooManyKings(Color),
+    /// The side not to move is in check, so the last move must have been illegal
+    OpponentInCheck,
+    InvalidCastlingRights,
+    InvalidEnPassant,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::MissingKing(Color::White) => "white has no king",
+            Self::MissingKing(Color::Black) => "black has no king",
+            Self::TooManyKings(Color::White) => "white has more than one king",
+            Self::TooManyKings(Color::Black) => "black has more than one king",
+            Self::OpponentInCheck => "the side not to move is in check",
+            Self::InvalidCastlingRights => "castling right without king and rook at home",
+            Self::InvalidEnPassant => "en passant square doesn't follow a double push",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for SetupError {}
+
+/// Assembles a position piece by piece, starting from an empty board with White to play
+/// and no castling rights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionBuilder {
+    board: Board,
+    to_play: Color,
+    castle_rights: (CastleRights, CastleRights),
+    en_passant: Option<Coord>,
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PositionBuilder {
+    pub fn new() -> Self {
+        let no_rights = CastleRights {
+            king: false,
+            queen: false,
+        };
+
+        Self {
+            board: Board::empty(),
+            to_play: Color::White,
+            castle_rights: (no_rights, no_rights),
+            en_passant: None,
+        }
+    }
+
+    /// Puts a piece on `coord`, replacing whatever was there
+    pub fn place(mut self, coord: Coord, piece: Piece, color: Color) -> Self {
+        self.board.set_square(coord, Square::Piece(piece, color));
+        self
+    }
+
+    pub fn to_play(mut self, color: Color) -> Self {
+        self.to_play = color;
+        self
+    }
+
+    pub fn castle_rights(mut self, player: Color, rights: CastleRights) -> Self {
+        match player {
+            Color::White => self.castle_rights.0 = rights,
+            Color::Black => self.castle_rights.1 = rights,
+        }
+        self
+    }
+
+    pub fn en_passant(mut self, coord: Option<Coord>) -> Self {
+        self.en_passant = coord;
+        self
+    }
+
+    pub fn build(self) -> Result<Position, SetupError> {
+        let king_coord = (self.find_king(Color::White)?, self.find_king(Color::Black)?);
+
+        let mut position = Position {
+            board: self.board,
+            to_play: self.to_play,
+            castle_rights: self.castle_rights,
+            castle_layout: STANDARD_LAYOUT,
+            en_passant: self.en_passant,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            state: State::Playing,
+            checks: vec![],
+            king_coord,
+            history: vec![],
+        };
+
+        let waiting = self.to_play.opponent();
+        if position.is_square_attacked(position.king_coord(waiting), waiting) {
+            return Err(SetupError::OpponentInCheck);
+        }
+
+        for player in [Color::White, Color::Black] {
+            if !self.has_castling_pieces(player) {
+                return Err(SetupError::InvalidCastlingRights);
+            }
+        }
+
+        if let Some(coord) = self.en_passant {
+            if !self.is_valid_en_passant(coord) {
+                return Err(SetupError::InvalidEnPassant);
+            }
+        }
+
+        position.update_state();
+
+        Ok(position)
+    }
+
+    fn find_king(&self, player: Color) -> Result<Coord, SetupError> {
+        let mut kings = mask_coords(self.board.piece_mask(Piece::King, player));
+
+        match (kings.next(), kings.next()) {
+            (Some(coord), None) => Ok(coord),
+            (None, _) => Err(SetupError::MissingKing(player)),
+            (Some(_), Some(_)) => Err(SetupError::TooManyKings(player)),
+        }
+    }
+
+    /// Whether the king and rooks are still on their home squares for each right claimed
+    fn has_castling_pieces(&self, player: Color) -> bool {
+        let rights = match player {
+            Color::White => self.castle_rights.0,
+            Color::Black => self.castle_rights.1,
+        };
+        let row = match player {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let on = |col: u8, piece: Piece| {
+            self.board[Coord::make(row, col)] == Square::Piece(piece, player)
+        };
+
+        if !rights.king && !rights.queen {
+            return true;
+        }
+
+        on(STANDARD_LAYOUT.king, Piece::King)
+            && (!rights.king || on(STANDARD_LAYOUT.king_rook, Piece::Rook))
+            && (!rights.queen || on(STANDARD_LAYOUT.queen_rook, Piece::Rook))
+    }
+
+    fn is_valid_en_passant(&self, coord: Coord) -> bool {
+        is_valid_en_passant(&self.board, self.to_play, coord)
+    }
+}
+
+/// Whether `coord` could be the en passant square with `to_play` to move: on the rank
+/// the opponent's pawn just skipped over, with that pawn in front of it and both the
+/// square and the one the pawn came from empty
+pub(super) fn is_valid_en_passant(board: &Board, to_play: Color, coord: Coord) -> bool {
+    let (row, pawn_row, start_row) = match to_play {
+        Color::White => (5, 4, 6),
+        Color::Black => (2, 3, 1),
+    };
+    let pawn = Square::Piece(Piece::Pawn, to_play.opponent());
+
+    coord.row == row
+        && coord.col < 8
+        && board[coord].is_empty()
+        && board[Coord::make(start_row, coord.col)].is_empty()
+        && board[Coord::make(pawn_row, coord.col)] == pawn
+}
+
+/// Most pieces besides the king that one side can have, as in the starting position
+const MAX_PIECES: u8 = 15;
+
+/// Non-pawn pieces each side starts with. Any more than these must be promoted pawns
+const STARTING_PIECES: [(Piece, u8); 4] = [
+    (Piece::Rook, 2),
+    (Piece::Knight, 2),
+    (Piece::Bishop, 2),
+    (Piece::Queen, 1),
+];
+
+impl Position {
+    /// A random position for fuzzing, drawing randomness from `rng`. Kings are never
+    /// adjacent, pawns stay off the back ranks and the side not to move is never in
+    /// check. Each side has at most fifteen pieces besides the king, and its pawns plus
+    /// any pieces beyond the starting set, which could only come from promotions, are
+    /// at most eight. Double checks are avoided altogether, as telling the reachable
+    /// ones apart isn't worth it. There are no castling rights or en passant squares
+    pub fn random_legal(mut rng: impl FnMut() -> u64) -> Position {
+        loop {
+            let white_king = random_coord(&mut rng);
+            let black_king = random_coord(&mut rng);
+            if white_king.chebyshev_distance(black_king) <= 1 {
+                continue;
+            }
+
+            let mut builder = PositionBuilder::new()
+                .place(white_king, Piece::King, Color::White)
+                .place(black_king, Piece::King, Color::Black);
+
+            for _ in 0..rng() % (2 * u64::from(MAX_PIECES) + 1) {
+                let coord = random_coord(&mut rng);
+                let color = random_color(&mut rng);
+                // Anything but the king, which comes last
+                let piece = Piece::ALL[(rng() % 5) as usize];
+
+                let pawn_misplaced = piece == Piece::Pawn && (coord.row == 0 || coord.row == 7);
+                if builder.board[coord].is_empty()
+                    && !pawn_misplaced
+                    && has_room_for(&builder.board, piece, color)
+                {
+                    builder = builder.place(coord, piece, color);
+                }
+            }
+
+            match builder.to_play(random_color(&mut rng)).build() {
+                Ok(position) if !position.is_double_check() => return position,
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Whether `color` could own one more `piece` than it has on `board` in a game that
+/// started from the standard position
+fn has_room_for(board: &Board, piece: Piece, color: Color) -> bool {
+    let mut counts = board.census()[color as usize];
+    counts[piece as usize] += 1;
+
+    let pieces: u8 = counts.iter().sum::<u8>() - counts[Piece::King as usize];
+    let promoted: u8 = STARTING_PIECES
+        .iter()
+        .map(|&(piece, start)| counts[piece as usize].saturating_sub(start))
+        .sum();
+
+    pieces <= MAX_PIECES && counts[Piece::Pawn as usize] + promoted <= 8
+}
+
+fn random_coord(rng: &mut impl FnMut() -> u64) -> Coord {
+    let i = (rng() % 64) as u8;
+    Coord::make(i / 8, i % 8)
+}
+
+fn random_color(rng: &mut impl FnMut() -> u64) -> Color {
+    if rng().is_multiple_of(2) {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coord(algebraic: &str) -> Coord {
+        Coord::from_algebraic(algebraic).unwrap()
+    }
+
+    #[test]
+    fn builds_queen_and_king_against_king() {
+        let position = PositionBuilder::new()
+            .place(coord("e1"), Piece::King, Color::White)
+            .place(coord("d1"), Piece::Queen, Color::White)
+            .place(coord("e8"), Piece::King, Color::Black)
+            .build()
+            .unwrap();
+
+        assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        assert_eq!(position.state(), State::Playing);
+    }
+
+    #[test]
+    fn rejects_two_white_kings() {
+        let result = PositionBuilder::new()
+            .place(coord("e1"), Piece::King, Color::White)
+            .place(coord("a1"), Piece::King, Color::White)
+            .place(coord("e8"), Piece::King, Color::Black)
+            .build();
+
+        assert_eq!(result, Err(SetupError::TooManyKings(Color::White)));
+    }
+
+    #[test]
+    fn rejects_impossible_setups() {
+        let kings = PositionBuilder::new()
+            .place(coord("e1"), Piece::King, Color::White)
+            .place(coord("e8"), Piece::King, Color::Black);
+
+        assert_eq!(
+            PositionBuilder::new()
+                .place(coord("e1"), Piece::King, Color::White)
+                .build(),
+            Err(SetupError::MissingKing(Color::Black))
+        );
+        assert_eq!(
+            kings
+                .clone()
+                .place(coord("e2"), Piece::Rook, Color::White)
+                .to_play(Color::White)
+                .build(),
+            Err(SetupError::OpponentInCheck)
+        );
+        assert_eq!(
+            kings
+                .clone()
+                .castle_rights(
+                    Color::White,
+                    CastleRights {
+                        king: true,
+                        queen: false
+                    }
+                )
+                .build(),
+            Err(SetupError::InvalidCastlingRights)
+        );
+        assert_eq!(
+            kings.en_passant(Some(coord("d6"))).build(),
+            Err(SetupError::InvalidEnPassant)
+        );
+    }
+
+    #[test]
+    fn random_positions_are_legal_and_round_trip() {
+        // splitmix64, for a reproducible stream
+        let mut state = 0x1234_5678_u64;
+        let mut rng = move || {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+
+        for _ in 0..1000 {
+            let position = Position::random_legal(&mut rng);
+            let fen = position.to_fen();
+
+            assert_eq!(Position::from_fen(&fen).unwrap().to_fen(), fen);
+            assert!(
+                position
+                    .king_coord(Color::White)
+                    .chebyshev_distance(position.king_coord(Color::Black))
+                    > 1
+            );
+            assert!(!position.is_double_check(), "{fen}");
+
+            for color in [Color::White, Color::Black] {
+                let counts = position.board().census()[color as usize];
+                let pieces: u8 = counts.iter().sum::<u8>() - counts[Piece::King as usize];
+                let promoted: u8 = STARTING_PIECES
+                    .iter()
+                    .map(|&(piece, start)| counts[piece as usize].saturating_sub(start))
+                    .sum();
+
+                assert!(pieces <= 15, "{fen}");
+                assert!(counts[Piece::Pawn as usize] + promoted <= 8, "{fen}");
+            }
+
+            let back_ranks = 0xFF00_0000_0000_00FF;
+            for color in [Color::White, Color::Black] {
+                assert_eq!(
+                    position.board().piece_mask(Piece::Pawn, color) & back_ranks,
+                    0,
+                    "{fen}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::vec;
+
+use crate::board::{Board, Color, Coord, Piece, Square};
+
+use super::{CastleLayout, CastleRights, Position, State};
+
+/// Knight placements among the five squares left after the bishops and queen
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Position {
+    /// Chess960 starting position by its Scharnagl number, where 518 is the standard setup
+    pub fn chess960(scharnagl_id: u16) -> Self {
+        assert!(scharnagl_id < 960, "Chess960 ids range from 0 to 959");
+
+        let mut rank = [None; 8];
+        let mut id = scharnagl_id as usize;
+
+        // Light-squared bishop on b, d, f or h, then the dark-squared one on a, c, e or g
+        rank[id % 4 * 2 + 1] = Some(Piece::Bishop);
+        id /= 4;
+        rank[id % 4 * 2] = Some(Piece::Bishop);
+        id /= 4;
+
+        place_nth_empty(&mut rank, id % 6, Piece::Queen);
+        id /= 6;
+
+        let (first, second) = KNIGHTS[id];
+        place_nth_empty(&mut rank, second, Piece::Knight);
+        place_nth_empty(&mut rank, first, Piece::Knight);
+
+        // The king always ends up between the two rooks
+        place_nth_empty(&mut rank, 0, Piece::Rook);
+        place_nth_empty(&mut rank, 0, Piece::King);
+        place_nth_empty(&mut rank, 0, Piece::Rook);
+
+        let rank = rank.map(Option::unwrap);
+        let col_of = |piece: Piece, nth: usize| {
+            (0..8).filter(|&col| rank[col] == piece).nth(nth).unwrap() as u8
+        };
+
+        let mut squares = [[Square::Empty; 8]; 8];
+        squares[0] = rank.map(|piece| Square::Piece(piece, Color::White));
+        squares[1] = [Square::Piece(Piece::Pawn, Color::White); 8];
+        squares[6] = [Square::Piece(Piece::Pawn, Color::Black); 8];
+        squares[7] = rank.map(|piece| Square::Piece(piece, Color::Black));
+        let board = Board::from_squares(squares);
+
+        let king_col = col_of(Piece::King, 0);
+        let rights = CastleRights {
+            king: true,
+            queen: true,
+        };
+
+        Self {
+            board,
+            castle_rights: (rights, rights),
+            castle_layout: CastleLayout {
+                king: king_col,
+                king_rook: col_of(Piece::Rook, 1),
+                queen_rook: col_of(Piece::Rook, 0),
+            },
+            to_play: Color::White,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            state: State::Playing,
+            checks: vec![],
+            king_coord: (Coord::make(0, king_col), Coord::make(7, king_col)),
+            history: vec![],
+        }
+    }
+}
+
+fn place_nth_empty(rank: &mut [Option<Piece>; 8], nth: usize, piece: Piece) {
+    let col = (0..8).filter(|&col| rank[col].is_none()).nth(nth).unwrap();
+    rank[col] = Some(piece);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::CastleSide;
+
+    fn coord(algebraic: &str) -> Coord {
+        Coord::from_algebraic(algebraic).unwrap()
+    }
+
+    #[test]
+    fn id_518_is_the_standard_position() {
+        assert_eq!(Position::chess960(518), Position::default());
+    }
+
+    #[test]
+    fn id_0_back_rank() {
+        let position = Position::chess960(0);
+        assert!(position
+            .to_fen()
+            .starts_with("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR"));
+    }
+
+    #[test]
+    fn castling_lands_on_the_standard_squares() {
+        let start =
+            Position::from_fen("1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w HBhb - 0 1").unwrap();
+        let king = Square::Piece(Piece::King, Color::White);
+        let rook = Square::Piece(Piece::Rook, Color::White);
+
+        let mut position = start.clone();
+        position.try_castle(CastleSide::Queen).unwrap();
+        assert_eq!(position.board()[coord("c1")], king);
+        assert_eq!(position.board()[coord("d1")], rook);
+        assert!(position.board()[coord("b1")].is_empty());
+        assert!(position.board()[coord("g1")].is_empty());
+
+        // The king already stands on g1, so only the rook moves
+        let mut position = start;
+        position.try_castle(CastleSide::King).unwrap();
+        assert_eq!(position.board()[coord("g1")], king);
+        assert_eq!(position.board()[coord("f1")], rook);
+        assert!(position.board()[coord("h1")].is_empty());
+    }
+}
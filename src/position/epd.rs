@@ -0,0 +1,224 @@
+use alloc::{format, string::String, string::ToString, vec::Vec};
+use core::fmt;
+
+use super::{FenError, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EpdError {
+    BadPosition(FenError),
+    /// An operation has operands but no opcode, or the opcode isn't an identifier
+    BadOpcode,
+    /// A quoted operand runs to the end of the line
+    UnterminatedString,
+    /// The `hmvc` or `fmvn` operation isn't a single number
+    BadCounter,
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadPosition(err) => write!(f, "bad position: {err}"),
+            Self::BadOpcode => f.write_str("operation needs an opcode"),
+            Self::UnterminatedString => f.write_str("unterminated string operand"),
+            Self::BadCounter => f.write_str("invalid move counter operation"),
+        }
+    }
+}
+
+impl core::error::Error for EpdError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::BadPosition(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Operations following the position in an EPD record, in the order given, such as
+/// `bm` (best move), `am` (avoid move) and `id`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct EpdOps {
+    ops: Vec<(String, Vec<String>)>,
+}
+
+impl EpdOps {
+    /// Operands of the first operation with this opcode, with quotes removed from strings
+    pub fn get(&self, opcode: &str) -> Option<&[String]> {
+        self.ops
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.ops
+            .iter()
+            .map(|(name, operands)| (name.as_str(), operands.as_slice()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    fn counter<T: core::str::FromStr>(&self, opcode: &str) -> Result<Option<T>, EpdError> {
+        match self.get(opcode) {
+            None => Ok(None),
+            Some([value]) => value.parse().map(Some).map_err(|_| EpdError::BadCounter),
+            Some(_) => Err(EpdError::BadCounter),
+        }
+    }
+}
+
+impl Position {
+    /// Parses the four position fields of an EPD record and the operations after them.
+    /// The move counters come from the `hmvc` and `fmvn` operations when present
+    pub fn from_epd(epd: &str) -> Result<(Position, EpdOps), EpdError> {
+        let epd = epd.trim_start();
+
+        // Split off the four position fields, leaving the operations untouched
+        let mut rest = epd;
+        let mut fields = Vec::new();
+        for _ in 0..4 {
+            let field_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if field_end == 0 {
+                return Err(EpdError::BadPosition(FenError::Truncated));
+            }
+            fields.push(&rest[..field_end]);
+            rest = rest[field_end..].trim_start();
+        }
+
+        let ops = parse_operations(rest)?;
+
+        let halfmove_clock = ops.counter::<u16>("hmvc")?.unwrap_or(0);
+        let fullmove_number = ops.counter::<u32>("fmvn")?.unwrap_or(1);
+
+        let fen = format!("{} {halfmove_clock} {fullmove_number}", fields.join(" "));
+        let position = Position::from_fen(&fen).map_err(EpdError::BadPosition)?;
+
+        Ok((position, ops))
+    }
+}
+
+/// Semicolon-terminated operations, each an opcode followed by whitespace-separated
+/// operands, where double-quoted operands may contain spaces and semicolons
+fn parse_operations(text: &str) -> Result<EpdOps, EpdError> {
+    let mut ops = EpdOps::default();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        match chars.next() {
+            None | Some(';') => {
+                let mut operation = tokens.drain(..);
+                if let Some(opcode) = operation.next() {
+                    let valid = opcode.starts_with(|c: char| c.is_ascii_alphabetic())
+                        && opcode
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if !valid {
+                        return Err(EpdError::BadOpcode);
+                    }
+
+                    ops.ops.push((opcode, operation.collect()));
+                }
+
+                if chars.peek().is_none() {
+                    return Ok(ops);
+                }
+            }
+            Some(c) if c.is_whitespace() => {}
+            Some('"') => {
+                let mut operand = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(EpdError::UnterminatedString),
+                        Some('"') => break,
+                        Some(c) => operand.push(c),
+                    }
+                }
+                // A quoted operand can't stand in for the opcode
+                if tokens.is_empty() {
+                    return Err(EpdError::BadOpcode);
+                }
+                tokens.push(operand);
+            }
+            Some(c) => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bratko_kopec_best_move_is_legal() {
+        let (position, ops) = Position::from_epd(
+            "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id \"BK.01\";",
+        )
+        .unwrap();
+
+        assert_eq!(ops.get("id"), Some(&["BK.01".to_string()][..]));
+
+        let best = ops.get("bm").unwrap();
+        assert_eq!(best, ["Qd1+"]);
+        let best = position.parse_san(&best[0]).unwrap();
+        assert!(position.legal_moves().contains(&best));
+        assert_eq!(best.to_uci(), "d6d1");
+    }
+
+    #[test]
+    fn operations_keep_their_order_and_counters_apply() {
+        let (position, ops) = Position::from_epd(
+            "4k3/8/8/8/8/8/8/4K3 w - - am Kd1 Kf1; c0 \"quoted; with spaces\"; hmvc 12; fmvn 40;",
+        )
+        .unwrap();
+
+        assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 12 40");
+        let opcodes: Vec<&str> = ops.iter().map(|(opcode, _)| opcode).collect();
+        assert_eq!(opcodes, ["am", "c0", "hmvc", "fmvn"]);
+        assert_eq!(
+            ops.get("am"),
+            Some(&["Kd1".to_string(), "Kf1".to_string()][..])
+        );
+        assert_eq!(
+            ops.get("c0"),
+            Some(&["quoted; with spaces".to_string()][..])
+        );
+        assert_eq!(ops.get("bm"), None);
+    }
+
+    #[test]
+    fn malformed_records_are_rejected() {
+        assert_eq!(
+            Position::from_epd("4k3/8/8/8/8/8/8/4K3 w -"),
+            Err(EpdError::BadPosition(FenError::Truncated))
+        );
+        assert_eq!(
+            Position::from_epd("4k3/8/8/8/8/8/8/4K3 w - - c0 \"open;"),
+            Err(EpdError::UnterminatedString)
+        );
+        assert_eq!(
+            Position::from_epd("4k3/8/8/8/8/8/8/4K3 w - - 1bad x;"),
+            Err(EpdError::BadOpcode)
+        );
+        assert_eq!(
+            Position::from_epd("4k3/8/8/8/8/8/8/4K3 w - - hmvc many;"),
+            Err(EpdError::BadCounter)
+        );
+        assert!(Position::from_epd("4k3/8/8/8/8/8/8/4K3 w - -")
+            .unwrap()
+            .1
+            .is_empty());
+    }
+}
@@ -0,0 +1,603 @@
+use alloc::{format, string::String, string::ToString, vec, vec::Vec};
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::board::{mask_coords, Board, Color, Coord, Piece, Square};
+
+use super::builder::is_valid_en_passant;
+use super::{CastleLayout, CastleRights, CastleSide, Position, State, STANDARD_LAYOUT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FenError {
+    Truncated,
+    BadRankCount,
+    BadFileCount,
+    BadPieceChar,
+    BadActiveColor,
+    BadCastling,
+    BadEnPassant,
+    BadHalfmoveClock,
+    BadFullmoveNumber,
+    MissingKing,
+    TooManyKings,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::Truncated => "missing FEN fields",
+            Self::BadRankCount => "piece placement must have eight ranks",
+            Self::BadFileCount => "rank does not have eight files",
+            Self::BadPieceChar => "invalid piece character",
+            Self::BadActiveColor => "active color must be `w` or `b`",
+            Self::BadCastling => "invalid castling availability",
+            Self::BadEnPassant => "invalid en passant square",
+            Self::BadHalfmoveClock => "invalid halfmove clock",
+            Self::BadFullmoveNumber => "invalid fullmove number",
+            Self::MissingKing => "each side needs a king",
+            Self::TooManyKings => "a side has more than one king",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for FenError {}
+
+impl Position {
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let mut next_field = || fields.next().ok_or(FenError::Truncated);
+
+        let board = parse_placement(next_field()?)?;
+
+        let to_play = match next_field()? {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::BadActiveColor),
+        };
+
+        let castling = next_field()?;
+
+        let en_passant = match next_field()? {
+            "-" => None,
+            field => match Coord::from_algebraic(field) {
+                Some(coord) if is_valid_en_passant(&board, to_play, coord) => Some(coord),
+                _ => return Err(FenError::BadEnPassant),
+            },
+        };
+
+        let halfmove_clock = next_field()?
+            .parse::<u16>()
+            .map_err(|_| FenError::BadHalfmoveClock)?;
+
+        let fullmove_number = next_field()?
+            .parse::<u32>()
+            .map_err(|_| FenError::BadFullmoveNumber)?;
+
+        let king_coord = (
+            find_king(&board, Color::White)?,
+            find_king(&board, Color::Black)?,
+        );
+
+        let (castle_rights, castle_layout) = parse_castling(castling, &board, king_coord)?;
+
+        let mut position = Self {
+            board,
+            to_play,
+            castle_rights,
+            castle_layout,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            state: State::Playing,
+            checks: vec![],
+            king_coord,
+            history: vec![],
+        };
+
+        position.update_state();
+
+        Ok(position)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for row in (0..8).rev() {
+            let mut empty = 0;
+
+            for col in 0..8 {
+                match self.board[Coord { row, col }] {
+                    Square::Empty => empty += 1,
+                    Square::Piece(piece, color) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_char(piece, color));
+                    }
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if row > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.to_play {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let mut castling = String::new();
+        for player in [Color::White, Color::Black] {
+            let rights = self.castle_rights(player);
+            let king = self.king_home(player);
+
+            for (has, side, letter, rook_col) in [
+                (
+                    rights.king,
+                    CastleSide::King,
+                    'k',
+                    self.castle_layout.king_rook,
+                ),
+                (
+                    rights.queen,
+                    CastleSide::Queen,
+                    'q',
+                    self.castle_layout.queen_rook,
+                ),
+            ] {
+                if !has {
+                    continue;
+                }
+
+                // Fall back to Shredder-FEN when `K`/`Q` would pick the wrong rook
+                let c = if outer_rook(&self.board, player, king, side) == Some(rook_col) {
+                    letter
+                } else {
+                    (b'a' + rook_col) as char
+                };
+
+                castling.push(match player {
+                    Color::White => c.to_ascii_uppercase(),
+                    Color::Black => c,
+                });
+            }
+        }
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        fen.push(' ');
+        match self.en_passant {
+            Some(coord) => fen.push_str(&coord.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+
+        fen
+    }
+}
+
+/// Checks the piece placement and side to move of a FEN string, including that each side
+/// has exactly one king, without building a `Position`. The remaining fields are only
+/// checked by `Position::from_fen`
+pub fn validate(fen: &str) -> Result<(), FenError> {
+    let mut fields = fen.split_whitespace();
+    let mut next_field = || fields.next().ok_or(FenError::Truncated);
+
+    let board = parse_placement(next_field()?)?;
+
+    if !matches!(next_field()?, "w" | "b") {
+        return Err(FenError::BadActiveColor);
+    }
+
+    for player in [Color::White, Color::Black] {
+        find_king(&board, player)?;
+    }
+
+    Ok(())
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Self::from_fen(fen)
+    }
+}
+
+fn parse_placement(field: &str) -> Result<Board, FenError> {
+    let mut board = Board::empty();
+
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::BadRankCount);
+    }
+
+    // FEN lists ranks from 8 down to 1
+    for (rank, row) in ranks.iter().zip((0..8).rev()) {
+        let mut col = 0;
+
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                if !(1..=8).contains(&skip) {
+                    return Err(FenError::BadPieceChar);
+                }
+                // Checked per digit, as a long run of digits would overflow the count
+                col += skip as u8;
+                if col > 8 {
+                    return Err(FenError::BadFileCount);
+                }
+                continue;
+            }
+
+            let square = parse_piece(c).ok_or(FenError::BadPieceChar)?;
+            board
+                .set_square(Coord { row, col }, square)
+                .ok_or(FenError::BadFileCount)?;
+            col += 1;
+        }
+
+        if col != 8 {
+            return Err(FenError::BadFileCount);
+        }
+    }
+
+    Ok(board)
+}
+
+fn parse_piece(c: char) -> Option<Square> {
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+
+    Some(Square::Piece(piece, color))
+}
+
+fn piece_char(piece: Piece, color: Color) -> char {
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+
+    match color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+fn parse_castling(
+    field: &str,
+    board: &Board,
+    king_coord: (Coord, Coord),
+) -> Result<((CastleRights, CastleRights), CastleLayout), FenError> {
+    let mut white = CastleRights {
+        king: false,
+        queen: false,
+    };
+    let mut black = white;
+    let mut layout = STANDARD_LAYOUT;
+
+    if field == "-" {
+        return Ok(((white, black), layout));
+    }
+
+    let (mut king_file, mut king_rook, mut queen_rook) = (None, None, None);
+
+    for c in field.chars() {
+        let (player, king, rights) = if c.is_ascii_uppercase() {
+            (Color::White, king_coord.0, &mut white)
+        } else {
+            (Color::Black, king_coord.1, &mut black)
+        };
+
+        let home_row = match player {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        if king.row != home_row {
+            return Err(FenError::BadCastling);
+        }
+
+        // `K` and `Q` name the outermost rook, while Shredder-FEN gives the rook's file
+        let (side, rook_col) = match c.to_ascii_lowercase() {
+            'k' => (
+                CastleSide::King,
+                outer_rook(board, player, king, CastleSide::King).unwrap_or(7),
+            ),
+            'q' => (
+                CastleSide::Queen,
+                outer_rook(board, player, king, CastleSide::Queen).unwrap_or(0),
+            ),
+            file @ 'a'..='h' => {
+                let col = file as u8 - b'a';
+                match col.cmp(&king.col) {
+                    Ordering::Greater => (CastleSide::King, col),
+                    Ordering::Less => (CastleSide::Queen, col),
+                    Ordering::Equal => return Err(FenError::BadCastling),
+                }
+            }
+            _ => return Err(FenError::BadCastling),
+        };
+
+        // Both colors share one layout, so every right has to agree on the files
+        let layout_rook = match side {
+            CastleSide::King => {
+                rights.king = true;
+                &mut king_rook
+            }
+            CastleSide::Queen => {
+                rights.queen = true;
+                &mut queen_rook
+            }
+        };
+        for (file, col) in [(&mut king_file, king.col), (layout_rook, rook_col)] {
+            if file.is_some_and(|file| file != col) {
+                return Err(FenError::BadCastling);
+            }
+            *file = Some(col);
+        }
+    }
+
+    layout.king = king_file.unwrap_or(layout.king);
+    layout.king_rook = king_rook.unwrap_or(layout.king_rook);
+    layout.queen_rook = queen_rook.unwrap_or(layout.queen_rook);
+
+    Ok(((white, black), layout))
+}
+
+/// The rook furthest from the king on the given side of its home row
+fn outer_rook(board: &Board, player: Color, king: Coord, side: CastleSide) -> Option<u8> {
+    let rook = |&col: &u8| {
+        board.square(Coord::make(king.row, col)) == Some(Square::Piece(Piece::Rook, player))
+    };
+
+    match side {
+        CastleSide::King => (king.col + 1..8).rev().find(rook),
+        CastleSide::Queen => (0..king.col).find(rook),
+    }
+}
+
+fn find_king(board: &Board, player: Color) -> Result<Coord, FenError> {
+    let mut kings = mask_coords(board.piece_mask(Piece::King, player));
+
+    match (kings.next(), kings.next()) {
+        (Some(coord), None) => Ok(coord),
+        (None, _) => Err(FenError::MissingKing),
+        (Some(_), Some(_)) => Err(FenError::TooManyKings),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn from_fen_loads_the_start_position() {
+        let position = Position::from_fen(START).unwrap();
+
+        assert_eq!(position, Position::standard());
+        assert_eq!(position.to_fen(), START);
+    }
+
+    #[test]
+    fn from_fen_loads_kiwipete() {
+        let position = Position::from_fen(KIWIPETE).unwrap();
+
+        assert_eq!(position.to_fen(), KIWIPETE);
+        assert_eq!(position.king_coord(Color::White), Coord::make(0, 4));
+        assert_eq!(position.king_coord(Color::Black), Coord::make(7, 4));
+        assert!(!position.is_in_check());
+    }
+
+    #[test]
+    fn from_fen_finds_checks() {
+        let position = Position::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(position.checkers(), [Coord::make(1, 4)]);
+    }
+
+    #[test]
+    fn from_fen_rejects_malformed_fields() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+                FenError::BadRankCount,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBXKBNR w KQkq - 0 1",
+                FenError::BadPieceChar,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::BadActiveColor,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                FenError::Truncated,
+            ),
+        ];
+
+        for (fen, err) in cases {
+            assert_eq!(Position::from_fen(fen), Err(err), "{fen}");
+        }
+    }
+
+    #[test]
+    fn long_runs_of_empty_squares_are_rejected() {
+        let fen = format!("{}/8/8/8/8/8/8/8 w - - 0 1", "8".repeat(33));
+
+        assert_eq!(validate(&fen), Err(FenError::BadFileCount));
+        assert_eq!(Position::from_fen(&fen), Err(FenError::BadFileCount));
+    }
+
+    #[test]
+    fn to_fen_round_trips() {
+        let fens = [
+            START,
+            KIWIPETE,
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 3 20",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 3",
+        ];
+
+        for fen in fens {
+            let position = Position::from_fen(fen).unwrap();
+
+            assert_eq!(position.to_fen(), fen);
+            assert_eq!(Position::from_fen(&position.to_fen()).unwrap(), position);
+        }
+    }
+
+    #[test]
+    fn castling_rights_must_share_one_layout() {
+        // The kings stand on different files, which one shared layout can't describe
+        assert_eq!(
+            Position::from_fen("r2k3r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"),
+            Err(FenError::BadCastling)
+        );
+        assert_eq!(
+            Position::from_fen("rr2k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").map(|_| ()),
+            Ok(())
+        );
+        assert_eq!(
+            Position::from_fen("4k3/8/8/8/8/8/8/RR2K2R w BHah - 0 1"),
+            Err(FenError::BadCastling)
+        );
+    }
+
+    #[test]
+    fn parse_delegates_to_from_fen() {
+        let position: Position = START.parse().unwrap();
+        assert_eq!(position, Position::standard());
+
+        assert_eq!(
+            "8/8/8 w - - 0 1".parse::<Position>(),
+            Err(FenError::BadRankCount)
+        );
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_placements() {
+        assert_eq!(validate(START), Ok(()));
+        assert_eq!(validate(KIWIPETE), Ok(()));
+        // Only the placement and side to move are checked
+        assert_eq!(validate("4k3/8/8/8/8/8/8/4K3 b"), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_bad_placements() {
+        let cases = [
+            (
+                "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::MissingKing,
+            ),
+            ("4k3/8/8/8/8/8/8/4K2K w - - 0 1", FenError::TooManyKings),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::BadPieceChar,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/4P4/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                FenError::BadFileCount,
+            ),
+            ("4k3/8/8/8/8/8/8/4K3 x - - 0 1", FenError::BadActiveColor),
+            ("4k3/8/8/8/8/8/8/4K3", FenError::Truncated),
+        ];
+
+        for (fen, err) in cases {
+            assert_eq!(validate(fen), Err(err), "{fen}");
+        }
+    }
+
+    #[test]
+    fn move_counters_survive_a_round_trip() {
+        let fen = "4k3/4p3/8/8/8/8/4P3/4K1N1 w - - 40 57";
+        let position = Position::from_fen(fen).unwrap();
+        assert_eq!(position.halfmove_clock(), 40);
+        assert_eq!(position.fullmove_number(), 57);
+        assert_eq!(position.to_fen(), fen);
+
+        let mut pawn_move = position.clone();
+        pawn_move
+            .try_move(Coord::make(1, 4), Coord::make(3, 4), None)
+            .unwrap();
+        assert_eq!(pawn_move.halfmove_clock(), 0);
+        assert!(pawn_move.to_fen().ends_with(" 0 57"));
+
+        let mut piece_move = position;
+        piece_move
+            .try_move(Coord::make(0, 6), Coord::make(2, 5), None)
+            .unwrap();
+        assert_eq!(piece_move.halfmove_clock(), 41);
+
+        let reloaded = Position::from_fen(&piece_move.to_fen()).unwrap();
+        assert_eq!(reloaded.halfmove_clock(), 41);
+        assert_eq!(reloaded.fullmove_number(), 57);
+    }
+
+    #[test]
+    fn en_passant_square_must_follow_a_double_push() {
+        let rejected = [
+            // The "pawn" that would be captured on e5 is the black king
+            "8/8/8/8/3Pk3/8/8/4K3 w - e5 0 1",
+            // No pawn in front of the square
+            "4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+            // Wrong rank for the side to move
+            "4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1",
+            // The square itself, or the one the pawn came from, is occupied
+            "4k3/8/3n4/3pP3/8/8/8/4K3 w - d6 0 1",
+            "3nk3/3n4/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            // The pawn in front is White's own
+            "4k3/8/8/3PP3/8/8/8/4K3 w - d6 0 1",
+        ];
+
+        for fen in rejected {
+            assert_eq!(
+                Position::from_fen(fen),
+                Err(FenError::BadEnPassant),
+                "{fen}"
+            );
+        }
+
+        let accepted = [
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1",
+        ];
+        for fen in accepted {
+            assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+}
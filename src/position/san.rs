@@ -0,0 +1,334 @@
+use alloc::{string::String, string::ToString, vec::Vec};
+use core::fmt;
+
+use crate::board::{Coord, Piece, Square};
+
+use super::{CastleSide, MoveInfo, Position, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SanError {
+    Malformed,
+    NoSuchPiece,
+    Illegal,
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::Malformed => "malformed move text",
+            Self::NoSuchPiece => "no piece can make that move",
+            Self::Illegal => "illegal move",
+            Self::Ambiguous => "ambiguous move",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for SanError {}
+
+impl Position {
+    pub fn move_to_san(&self, mv: &MoveInfo) -> String {
+        let mut san = String::new();
+
+        let (from, from_square) = mv.from;
+        let to = mv.to.0;
+
+        match mv.castle() {
+            Some(CastleSide::King) => san.push_str("O-O"),
+            Some(CastleSide::Queen) => san.push_str("O-O-O"),
+            None => {
+                let piece = from_square.piece_kind().unwrap();
+
+                if piece == Piece::Pawn {
+                    if mv.captures().is_some() {
+                        san.push(file_char(from.col));
+                    }
+                } else {
+                    san.push(piece_letter(piece));
+
+                    // Other pieces of the same kind that could also reach `to`
+                    let rivals: Vec<_> = self
+                        .legal_moves()
+                        .into_iter()
+                        .filter(|other| {
+                            other.from.1 == from_square && other.to.0 == to && other.from.0 != from
+                        })
+                        .map(|other| other.from.0)
+                        .collect();
+
+                    // Prefer the file, then the rank, then the full square
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|rival| rival.col != from.col) {
+                            san.push(file_char(from.col));
+                        } else if rivals.iter().all(|rival| rival.row != from.row) {
+                            san.push(rank_char(from.row));
+                        } else {
+                            san.push_str(&from.to_string());
+                        }
+                    }
+                }
+
+                if mv.captures().is_some() {
+                    san.push('x');
+                }
+
+                san.push_str(&to.to_string());
+
+                if let Some(promotion) = mv.promotion() {
+                    san.push('=');
+                    san.push(piece_letter(promotion));
+                }
+            }
+        }
+
+        let mut next = self.clone();
+        if next.try_move(from, to, mv.promotion()).is_ok() {
+            if let State::Checkmate(_) = next.state() {
+                san.push('#');
+            } else if next.is_in_check() {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<MoveInfo, SanError> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let castle = match san {
+            "O-O" | "0-0" => Some(CastleSide::King),
+            "O-O-O" | "0-0-0" => Some(CastleSide::Queen),
+            _ => None,
+        };
+
+        if let Some(side) = castle {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.castle() == Some(side))
+                .ok_or(SanError::Illegal);
+        }
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((rest, piece)) => {
+                let mut chars = piece.chars();
+                let piece = chars.next().and_then(parse_piece_letter);
+                if piece.is_none() || chars.next().is_some() {
+                    return Err(SanError::Malformed);
+                }
+                (rest, piece)
+            }
+            None => (san, None),
+        };
+
+        let (piece, san) = match san.chars().next().and_then(parse_piece_letter) {
+            Some(piece) => (piece, &san[1..]),
+            None => (Piece::Pawn, san),
+        };
+
+        if san.len() < 2 || !san.is_ascii() {
+            return Err(SanError::Malformed);
+        }
+
+        let (hint, to) = san.split_at(san.len() - 2);
+        let to = Coord::from_algebraic(to).ok_or(SanError::Malformed)?;
+        let hint = hint.strip_suffix('x').unwrap_or(hint);
+
+        // Disambiguation may give the origin file, rank, or both
+        let mut file = None;
+        let mut rank = None;
+        for c in hint.chars() {
+            match c {
+                'a'..='h' if file.is_none() && rank.is_none() => file = Some(c as u8 - b'a'),
+                '1'..='8' if rank.is_none() => rank = Some(c as u8 - b'1'),
+                _ => return Err(SanError::Malformed),
+            }
+        }
+
+        let origin_matches = |coord: Coord| {
+            file.is_none_or(|col| coord.col == col) && rank.is_none_or(|row| coord.row == row)
+        };
+
+        let own_piece = Square::Piece(piece, self.to_play);
+        let has_piece = (0..8)
+            .flat_map(|row| (0..8).map(move |col| Coord { row, col }))
+            .any(|coord| origin_matches(coord) && self.board.square(coord) == Some(own_piece));
+
+        if !has_piece {
+            return Err(SanError::NoSuchPiece);
+        }
+
+        let mut candidates = self.legal_moves().into_iter().filter(|mv| {
+            mv.castle().is_none()
+                && mv.from.1 == own_piece
+                && mv.to.0 == to
+                && mv.promotion() == promotion
+                && origin_matches(mv.from.0)
+        });
+
+        let mv = candidates.next().ok_or(SanError::Illegal)?;
+        if candidates.next().is_some() {
+            return Err(SanError::Ambiguous);
+        }
+
+        Ok(mv)
+    }
+}
+
+fn piece_letter(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+fn parse_piece_letter(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+fn file_char(col: u8) -> char {
+    (b'a' + col) as char
+}
+
+fn rank_char(row: u8) -> char {
+    (b'1' + row) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Color;
+
+    fn san(fen: &str, from: &str, to: &str, promotion: Option<Piece>) -> String {
+        let position = Position::from_fen(fen).unwrap();
+        let from = Coord::from_algebraic(from).unwrap();
+        let to = Coord::from_algebraic(to).unwrap();
+
+        position.move_to_san(&position.can_move(from, to, promotion).unwrap())
+    }
+
+    #[test]
+    fn disambiguates_by_file() {
+        let fen = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+
+        assert_eq!(san(fen, "b1", "d2", None), "Nbd2");
+        assert_eq!(san(fen, "f3", "d2", None), "Nfd2");
+        assert_eq!(san(fen, "b1", "c3", None), "Nc3");
+    }
+
+    #[test]
+    fn disambiguates_rooks_on_one_rank() {
+        let fen = "7k/8/8/8/8/8/8/R4RK1 w - - 0 1";
+
+        assert_eq!(san(fen, "f1", "e1", None), "Rfe1");
+        assert_eq!(san(fen, "a1", "e1", None), "Rae1");
+    }
+
+    #[test]
+    fn disambiguates_by_rank() {
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+
+        assert_eq!(san(fen, "a1", "a3", None), "R1a3");
+        assert_eq!(san(fen, "a5", "a3", None), "R5a3");
+    }
+
+    #[test]
+    fn pawn_captures_name_the_file() {
+        let fen = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1";
+
+        assert_eq!(san(fen, "e4", "d5", None), "exd5");
+    }
+
+    #[test]
+    fn promotions_with_check() {
+        let fen = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+
+        assert_eq!(san(fen, "e7", "e8", Some(Piece::Queen)), "e8=Q+");
+        assert_eq!(san(fen, "e7", "e8", Some(Piece::Knight)), "e8=N");
+    }
+
+    #[test]
+    fn castles_and_mates() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1", "c1", None), "O-O-O");
+        assert_eq!(san(fen, "e1", "g1", None), "O-O");
+
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(san(fen, "a1", "a8", None), "Ra8#");
+    }
+
+    #[test]
+    fn parses_a_short_game() {
+        let game = [
+            ("e4", "e2", "e4"),
+            ("e5", "e7", "e5"),
+            ("Nf3", "g1", "f3"),
+            ("Nc6", "b8", "c6"),
+            ("Bb5", "f1", "b5"),
+            ("a6", "a7", "a6"),
+            ("Bxc6", "b5", "c6"),
+            ("dxc6", "d7", "c6"),
+            ("O-O", "e1", "g1"),
+            ("Bg4", "c8", "g4"),
+            ("h3", "h2", "h3"),
+            ("h5!?", "h7", "h5"),
+            ("hxg4", "h3", "g4"),
+            ("hxg4", "h5", "g4"),
+            ("Ne1", "f3", "e1"),
+            ("Qh4", "d8", "h4"),
+            ("d4", "d2", "d4"),
+            ("Qh2#", "h4", "h2"),
+        ];
+
+        let mut position = Position::standard();
+        for (san, from, to) in game {
+            let from = Coord::from_algebraic(from).unwrap();
+            let to = Coord::from_algebraic(to).unwrap();
+
+            let mv = position.parse_san(san).unwrap();
+            assert!(position.legal_moves().contains(&mv), "{san}");
+            assert_eq!((mv.from().0, mv.to().0), (from, to), "{san}");
+
+            position.try_move(from, to, None).unwrap();
+        }
+
+        assert_eq!(position.state(), State::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn parses_promotions_and_disambiguation() {
+        let position = Position::from_fen("4k3/P7/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+
+        let mv = position.parse_san("a8=N").unwrap();
+        assert_eq!(mv.promotion(), Some(Piece::Knight));
+
+        let mv = position.parse_san("Nfd2").unwrap();
+        assert_eq!(mv.from().0, Coord::from_algebraic("f3").unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let position = Position::from_fen("4k3/P7/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+
+        assert_eq!(position.parse_san("Nd2"), Err(SanError::Ambiguous));
+        assert_eq!(position.parse_san("Qd2"), Err(SanError::NoSuchPiece));
+        assert_eq!(position.parse_san("Nd5"), Err(SanError::Illegal));
+        assert_eq!(position.parse_san("a8"), Err(SanError::Illegal));
+        assert_eq!(position.parse_san("N"), Err(SanError::Malformed));
+        assert_eq!(position.parse_san("a8=X"), Err(SanError::Malformed));
+    }
+}
@@ -0,0 +1,157 @@
+use alloc::{format, string::String, string::ToString};
+use core::fmt;
+
+use crate::board::{Coord, Piece};
+
+use super::{MoveErr, MoveInfo, Position};
+
+/// A move in a UCI move list that couldn't be played
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UciLineError {
+    /// The offending move as written
+    pub token: String,
+    pub error: MoveErr,
+}
+
+impl fmt::Display for UciLineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot play `{}`: {}", self.token, self.error)
+    }
+}
+
+impl core::error::Error for UciLineError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl MoveInfo {
+    pub fn to_uci(&self) -> String {
+        // Castling is already stored as the king's two-square move
+        let mut uci = format!("{}{}", self.from.0, self.to.0);
+
+        if let Some(piece) = self.promotion() {
+            uci.push(match piece {
+                Piece::Pawn => 'p',
+                Piece::Knight => 'n',
+                Piece::Bishop => 'b',
+                Piece::Rook => 'r',
+                Piece::Queen => 'q',
+                Piece::King => 'k',
+            });
+        }
+
+        uci
+    }
+}
+
+impl Position {
+    pub fn parse_uci_move(&self, uci: &str) -> Result<MoveInfo, MoveErr> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(MoveErr::OutOfBounds);
+        }
+
+        let from = Coord::from_algebraic(&uci[0..2]).ok_or(MoveErr::OutOfBounds)?;
+        let to = Coord::from_algebraic(&uci[2..4]).ok_or(MoveErr::OutOfBounds)?;
+
+        let promotion = match uci[4..].chars().next() {
+            None => None,
+            Some('n') => Some(Piece::Knight),
+            Some('b') => Some(Piece::Bishop),
+            Some('r') => Some(Piece::Rook),
+            Some('q') => Some(Piece::Queen),
+            Some(_) => return Err(MoveErr::InvalidPromotion),
+        };
+
+        self.can_move(from, to, promotion)
+    }
+
+    /// Plays a space-separated list of UCI moves such as `e2e4 e7e5 g1f3` in order,
+    /// stopping at the first one that can't be played with the earlier ones kept
+    pub fn apply_uci_line(&mut self, moves: &str) -> Result<(), UciLineError> {
+        for token in moves.split_whitespace() {
+            self.parse_uci_move(token)
+                .and_then(|piece_move| {
+                    self.try_move(piece_move.from.0, piece_move.to.0, piece_move.promotion())
+                })
+                .map_err(|error| UciLineError {
+                    token: token.to_string(),
+                    error,
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uci_round_trips_every_legal_move() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ];
+
+        for fen in fens {
+            let position = Position::from_fen(fen).unwrap();
+
+            for piece_move in position.legal_moves() {
+                let uci = piece_move.to_uci();
+                assert_eq!(position.parse_uci_move(&uci), Ok(piece_move), "{uci}");
+            }
+        }
+    }
+
+    #[test]
+    fn uci_formats_castles_and_promotions() {
+        let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        assert_eq!(position.parse_uci_move("e1g1").unwrap().to_uci(), "e1g1");
+        assert_eq!(position.parse_uci_move("b7a8q").unwrap().to_uci(), "b7a8q");
+        assert_eq!(
+            position.parse_uci_move("b7b8x"),
+            Err(MoveErr::InvalidPromotion)
+        );
+        assert_eq!(position.parse_uci_move("e1"), Err(MoveErr::OutOfBounds));
+    }
+
+    #[test]
+    fn apply_uci_line_replays_an_opening() {
+        let mut position = Position::standard();
+
+        position
+            .apply_uci_line(
+                "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 \
+                 e1g1 f8e7 f1e1 b7b5 a4b3 d7d6 c2c3 e8g8",
+            )
+            .unwrap();
+
+        assert_eq!(
+            position.to_fen(),
+            "r1bq1rk1/2p1bppp/p1np1n2/1p2p3/4P3/1BP2N2/PP1P1PPP/RNBQR1K1 w - - 1 9"
+        );
+    }
+
+    #[test]
+    fn apply_uci_line_stops_at_the_first_bad_move() {
+        let mut position = Position::standard();
+
+        let err = position.apply_uci_line("e2e4 e7e5 e1e3 g1f3").unwrap_err();
+
+        assert_eq!(err.token, "e1e3");
+        assert_eq!(
+            err.to_string(),
+            format!("cannot play `e1e3`: {}", err.error)
+        );
+        // The moves before the bad one stay played
+        assert_eq!(
+            position.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+    }
+}
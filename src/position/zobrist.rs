@@ -0,0 +1,104 @@
+use crate::board::Color;
+
+use super::Position;
+
+const PIECE_KEYS: usize = 2 * 6 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+const CASTLE_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLE_KEYS + 4;
+const KEY_COUNT: usize = EN_PASSANT_KEYS + 8;
+
+/// Pseudo-random keys generated at compile time with splitmix64
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    keys
+};
+
+impl Position {
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+
+        for (coord, piece, color) in self.board.pieces() {
+            let square = (coord.row * 8 + coord.col) as usize;
+            hash ^= KEYS[(color as usize * 6 + piece as usize) * 64 + square];
+        }
+
+        if self.to_play == Color::Black {
+            hash ^= KEYS[SIDE_KEY];
+        }
+
+        let (white, black) = self.castle_rights;
+        let rights = [white.king, white.queen, black.king, black.queen];
+        for (i, _) in rights.iter().enumerate().filter(|(_, has)| **has) {
+            hash ^= KEYS[CASTLE_KEYS + i];
+        }
+
+        if let Some(coord) = self.en_passant {
+            hash ^= KEYS[EN_PASSANT_KEYS + coord.col as usize];
+        }
+
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::board::Coord;
+
+    fn play(position: &mut Position, moves: &[(&str, &str)]) {
+        for (from, to) in moves {
+            let from = Coord::from_algebraic(from).unwrap();
+            let to = Coord::from_algebraic(to).unwrap();
+            position.try_move(from, to, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn transpositions_share_a_key() {
+        let mut knights_first = Position::standard();
+        play(
+            &mut knights_first,
+            &[("g1", "f3"), ("g8", "f6"), ("b1", "c3"), ("b8", "c6")],
+        );
+
+        let mut queenside_first = Position::standard();
+        play(
+            &mut queenside_first,
+            &[("b1", "c3"), ("b8", "c6"), ("g1", "f3"), ("g8", "f6")],
+        );
+
+        assert_eq!(knights_first.zobrist(), queenside_first.zobrist());
+        assert_ne!(knights_first.zobrist(), Position::standard().zobrist());
+    }
+
+    #[test]
+    fn undo_restores_the_key() {
+        let mut position = Position::standard();
+        let mut keys = vec![position.zobrist()];
+
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5"), ("e8", "d7")] {
+            play(&mut position, &[(from, to)]);
+            keys.push(position.zobrist());
+        }
+
+        while position.undo().is_some() {
+            keys.pop();
+            assert_eq!(position.zobrist(), *keys.last().unwrap());
+        }
+        assert_eq!(keys.len(), 1);
+    }
+}
@@ -0,0 +1,413 @@
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+use crate::board::{Board, Color, Coord, Piece, Square};
+use crate::position::Position;
+
+// ANSI select graphic rendition codes
+const LIGHT_SQUARE: &str = "48;5;180";
+const DARK_SQUARE: &str = "48;5;94";
+const WHITE_PIECE: &str = "1;97";
+const BLACK_PIECE: &str = "1;30";
+const RESET: &str = "\x1b[0m";
+
+/// SVG square size and the margin left for coordinate labels, in pixels
+const SVG_SQUARE: u32 = 45;
+const SVG_MARGIN: u32 = 20;
+
+/// Draws the board as text, with `perspective` at the bottom
+pub fn render_board(board: &Board, perspective: Color, unicode: bool) -> String {
+    render(board, perspective, unicode, &[])
+}
+
+/// ASCII rendering with `perspective` at the bottom
+pub fn board_to_string(board: &Board, perspective: Color) -> String {
+    render(board, perspective, false, &[])
+}
+
+/// ASCII rendering with each square in `highlight` bracketed, such as the last move's
+/// from and to squares
+pub fn board_to_string_highlighted(
+    board: &Board,
+    perspective: Color,
+    highlight: &[Coord],
+) -> String {
+    render(board, perspective, false, highlight)
+}
+
+/// Board with ANSI background colors for the squares and foreground colors for the
+/// pieces, with `perspective` at the bottom
+pub fn render_board_ansi(board: &Board, perspective: Color) -> String {
+    let (rows, cols) = orientation(perspective);
+    let mut rendered = String::new();
+
+    for &row in &rows {
+        rendered.push_str(&format!("{} ", row + 1));
+
+        for &col in &cols {
+            let coord = Coord { row, col };
+            let background = match coord.square_color() {
+                Color::White => LIGHT_SQUARE,
+                Color::Black => DARK_SQUARE,
+            };
+
+            let (foreground, c) = match board[coord] {
+                Square::Empty => (WHITE_PIECE, ' '),
+                square @ Square::Piece(_, Color::White) => (WHITE_PIECE, square_char(square)),
+                square @ Square::Piece(_, Color::Black) => (BLACK_PIECE, square_char(square)),
+            };
+
+            rendered.push_str(&format!("\x1b[{background};{foreground}m {c} "));
+        }
+
+        rendered.push_str(RESET);
+        rendered.push('\n');
+    }
+
+    rendered.push_str("  ");
+    for &col in &cols {
+        rendered.push_str(&format!(" {} ", (b'a' + col) as char));
+    }
+    rendered.push('\n');
+
+    rendered
+}
+
+/// ANSI rendering when standard output is a terminal and `NO_COLOR` isn't set, and the
+/// plain ASCII one otherwise so piped output stays free of escape codes
+pub fn render_board_for_terminal(board: &Board, perspective: Color) -> String {
+    if io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+        render_board_ansi(board, perspective)
+    } else {
+        board_to_string(board, perspective)
+    }
+}
+
+fn render(board: &Board, perspective: Color, unicode: bool, highlight: &[Coord]) -> String {
+    let (rows, cols) = orientation(perspective);
+    let mut rendered = String::new();
+
+    for &row in &rows {
+        rendered.push_str(&format!("{} ", row + 1));
+
+        // Brackets take the place of the spaces between squares, so neighbouring
+        // highlights share a `|`
+        let mut previous = false;
+
+        for &col in &cols {
+            let coord = Coord { row, col };
+            let highlighted = highlight.contains(&coord);
+
+            rendered.push(match (previous, highlighted) {
+                (false, false) => ' ',
+                (false, true) => '[',
+                (true, false) => ']',
+                (true, true) => '|',
+            });
+            rendered.push(if unicode {
+                square_glyph(board[coord])
+            } else {
+                square_char(board[coord])
+            });
+
+            previous = highlighted;
+        }
+
+        if previous {
+            rendered.push(']');
+        }
+        rendered.push('\n');
+    }
+
+    rendered.push_str("  ");
+    for &col in &cols {
+        rendered.push(' ');
+        rendered.push((b'a' + col) as char);
+    }
+    rendered.push('\n');
+
+    rendered
+}
+
+impl Position {
+    /// Standalone SVG image of the board from White's side, with Unicode piece glyphs
+    /// and file and rank labels
+    pub fn to_svg(&self) -> String {
+        let size = SVG_MARGIN + 8 * SVG_SQUARE;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+             viewBox=\"0 0 {size} {size}\" font-family=\"sans-serif\">\n"
+        );
+
+        for row in 0..8 {
+            for col in 0..8 {
+                let coord = Coord { row, col };
+                let x = SVG_MARGIN + col as u32 * SVG_SQUARE;
+                let y = (7 - row as u32) * SVG_SQUARE;
+                let fill = match coord.square_color() {
+                    Color::White => "#f0d9b5",
+                    Color::Black => "#b58863",
+                };
+
+                svg.push_str(&format!(
+                    "  <rect x=\"{x}\" y=\"{y}\" width=\"{SVG_SQUARE}\" \
+                     height=\"{SVG_SQUARE}\" fill=\"{fill}\"/>\n"
+                ));
+            }
+        }
+
+        for (coord, piece, color) in self.board().pieces() {
+            let x = SVG_MARGIN + coord.col as u32 * SVG_SQUARE + SVG_SQUARE / 2;
+            let y = (7 - coord.row as u32) * SVG_SQUARE + SVG_SQUARE / 2;
+
+            svg.push_str(&format!(
+                "  <text x=\"{x}\" y=\"{y}\" font-size=\"36\" text-anchor=\"middle\" \
+                 dominant-baseline=\"central\">{}</text>\n",
+                square_glyph(Square::Piece(piece, color))
+            ));
+        }
+
+        for i in 0..8 {
+            let offset = i * SVG_SQUARE + SVG_SQUARE / 2;
+
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\" \
+                 dominant-baseline=\"central\">{}</text>\n",
+                SVG_MARGIN / 2,
+                offset,
+                8 - i
+            ));
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\" \
+                 dominant-baseline=\"central\">{}</text>\n",
+                SVG_MARGIN + offset,
+                8 * SVG_SQUARE + SVG_MARGIN / 2,
+                (b'a' + i as u8) as char
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+impl fmt::Display for Board {
+    /// Renders from White's side in ASCII, or with Unicode glyphs using `{:#}`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&render_board(self, Color::White, f.alternate()))
+    }
+}
+
+/// Rows from top to bottom and columns from left to right. White sees rank 8 at the top
+/// and the a-file on the left, Black the opposite
+fn orientation(perspective: Color) -> (Vec<u8>, Vec<u8>) {
+    match perspective {
+        Color::White => ((0..8).rev().collect(), (0..8).collect()),
+        Color::Black => ((0..8).collect(), (0..8).rev().collect()),
+    }
+}
+
+fn square_char(square: Square) -> char {
+    let (piece, color) = match square {
+        Square::Empty => return '.',
+        Square::Piece(piece, color) => (piece, color),
+    };
+
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+
+    match color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+fn square_glyph(square: Square) -> char {
+    match square {
+        Square::Empty => '.',
+        Square::Piece(piece, Color::White) => match piece {
+            Piece::Pawn => '♙',
+            Piece::Knight => '♘',
+            Piece::Bishop => '♗',
+            Piece::Rook => '♖',
+            Piece::Queen => '♕',
+            Piece::King => '♔',
+        },
+        Square::Piece(piece, Color::Black) => match piece {
+            Piece::Pawn => '♟',
+            Piece::Knight => '♞',
+            Piece::Bishop => '♝',
+            Piece::Rook => '♜',
+            Piece::Queen => '♛',
+            Piece::King => '♚',
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        *Position::from_fen(fen).unwrap().board()
+    }
+
+    #[test]
+    fn renders_from_either_side() {
+        let board = board("4k3/8/8/8/4P3/8/8/R3K2R b KQ e3 0 1");
+
+        assert_eq!(
+            board_to_string(&board, Color::White),
+            "8  . . . . k . . .\n\
+             7  . . . . . . . .\n\
+             6  . . . . . . . .\n\
+             5  . . . . . . . .\n\
+             4  . . . . P . . .\n\
+             3  . . . . . . . .\n\
+             2  . . . . . . . .\n\
+             1  R . . . K . . R\n   \
+             a b c d e f g h\n"
+        );
+        assert_eq!(
+            board_to_string(&board, Color::Black),
+            "1  R . . K . . . R\n\
+             2  . . . . . . . .\n\
+             3  . . . . . . . .\n\
+             4  . . . P . . . .\n\
+             5  . . . . . . . .\n\
+             6  . . . . . . . .\n\
+             7  . . . . . . . .\n\
+             8  . . . k . . . .\n   \
+             h g f e d c b a\n"
+        );
+    }
+
+    #[test]
+    fn unicode_glyphs_sit_in_their_cells() {
+        let board = board("4k3/8/8/3p4/8/5N2/8/4K3 w - - 0 1");
+        let rendered = render_board(&board, Color::White, true);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        // Each cell is a separator followed by the piece, after the two label columns
+        let cell = |line: &str, col: usize| line.chars().nth(3 + col * 2).unwrap();
+
+        assert_eq!(cell(lines[5], 5), '♘');
+        assert_eq!(cell(lines[3], 3), '♟');
+        assert_eq!(cell(lines[0], 4), '♚');
+        assert!(!rendered.contains('N'));
+
+        // ASCII stays the default
+        assert_eq!(
+            cell(
+                board_to_string(&board, Color::White)
+                    .lines()
+                    .nth(5)
+                    .unwrap(),
+                5
+            ),
+            'N'
+        );
+    }
+
+    #[test]
+    fn start_position_cases_by_rank() {
+        let rendered = board_to_string(Position::default().board(), Color::White);
+        let ranks: Vec<String> = rendered
+            .lines()
+            .take(8)
+            .map(|line| line[2..].replace([' ', '.'], ""))
+            .collect();
+
+        assert_eq!(ranks[0], "rnbqkbnr");
+        assert_eq!(ranks[1], "pppppppp");
+        assert_eq!(ranks[6], "PPPPPPPP");
+        assert_eq!(ranks[7], "RNBQKBNR");
+        assert!(ranks[2..6].iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn highlights_bracket_their_squares() {
+        let mut position = Position::default();
+        position
+            .try_move(
+                Coord::from_algebraic("e2").unwrap(),
+                Coord::from_algebraic("e4").unwrap(),
+                None,
+            )
+            .unwrap();
+        let last_move = [
+            Coord::from_algebraic("e2").unwrap(),
+            Coord::from_algebraic("e4").unwrap(),
+        ];
+
+        let plain = board_to_string(position.board(), Color::White);
+        let highlighted = board_to_string_highlighted(position.board(), Color::White, &last_move);
+
+        assert_ne!(plain, highlighted);
+        assert_eq!(highlighted.lines().nth(4), Some("4  . . . .[P]. . ."));
+        assert_eq!(highlighted.lines().nth(6), Some("2  P P P P[.]P P P"));
+
+        // Removing the brackets leaves the plain rendering
+        assert_eq!(highlighted.replace(['[', ']'], " "), plain);
+        assert_eq!(
+            board_to_string_highlighted(position.board(), Color::White, &[]),
+            plain
+        );
+    }
+
+    #[test]
+    fn ansi_rendering_colors_squares_and_pieces() {
+        let board = *Position::default().board();
+        let ansi = render_board_ansi(&board, Color::White);
+
+        assert!(ansi.contains(&format!("\x1b[{LIGHT_SQUARE};{BLACK_PIECE}m r ")));
+        assert!(ansi.contains(&format!("\x1b[{DARK_SQUARE};{WHITE_PIECE}m R ")));
+        assert_eq!(ansi.matches(RESET).count(), 8);
+
+        // Stripping the escape codes leaves every piece letter in place, then the files
+        let letters: String = ansi
+            .split('\x1b')
+            .filter_map(|part| part.split_once('m').map(|(_, text)| text))
+            .collect::<String>()
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .collect();
+        assert_eq!(letters, "rnbqkbnrppppppppPPPPPPPPRNBQKBNRabcdefgh");
+    }
+
+    #[test]
+    fn plain_rendering_has_no_escape_codes() {
+        let board = *Position::default().board();
+
+        // Whether or not the tests run in a terminal, NO_COLOR forces the plain path
+        std::env::set_var("NO_COLOR", "1");
+        let rendered = render_board_for_terminal(&board, Color::White);
+        assert!(!rendered.contains('\x1b'));
+        assert_eq!(rendered, board_to_string(&board, Color::White));
+    }
+
+    #[test]
+    fn svg_has_a_rect_per_square_and_a_glyph_per_piece() {
+        let svg = Position::default().to_svg();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 64);
+        assert_eq!(svg.matches("fill=\"#f0d9b5\"").count(), 32);
+
+        // 32 pieces plus eight rank and eight file labels
+        assert_eq!(svg.matches("<text ").count(), 48);
+        assert_eq!(svg.matches('♙').count(), 8);
+        assert_eq!(svg.matches('♚').count(), 1);
+
+        let lone_kings = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(lone_kings.to_svg().matches("<text ").count(), 18);
+    }
+}
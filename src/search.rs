@@ -0,0 +1,612 @@
+mod table;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::Color;
+use crate::eval::{piece_value, MATE_SCORE};
+use crate::position::{MoveInfo, Position, State};
+
+use table::{Bound, Entry, TranspositionTable};
+
+/// Scores this close to `MATE_SCORE` encode a distance to mate
+const MATE_THRESHOLD: i32 = MATE_SCORE - 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Number of transposition table entries, where 0 disables the table
+    pub table_size: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            table_size: 1 << 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchReport {
+    pub best_move: Option<MoveInfo>,
+    /// Score from the side to play's perspective
+    pub score: i32,
+    /// Positions visited during the search
+    pub nodes: u64,
+}
+
+impl Position {
+    pub fn best_move(&self, depth: u32) -> Option<MoveInfo> {
+        self.search_with(depth, &SearchOptions::default()).best_move
+    }
+
+    pub fn search_with(&self, depth: u32, options: &SearchOptions) -> SearchReport {
+        let mut searcher = Searcher::new(options);
+        let (best_move, score) = searcher.root(&mut self.clone(), depth, None);
+
+        SearchReport {
+            best_move,
+            score,
+            nodes: searcher.nodes,
+        }
+    }
+
+    /// Searches one ply deeper at a time until `max_millis` runs out, returning the best
+    /// move of the deepest search that finished
+    pub fn best_move_timed(&self, max_millis: u64) -> Option<MoveInfo> {
+        let mut searcher = Searcher::new(&SearchOptions::default());
+        searcher.deadline = Some(Instant::now() + Duration::from_millis(max_millis));
+
+        self.deepen(&mut searcher, u32::MAX)
+    }
+
+    /// Searches to `depth` one ply at a time, giving up once `stop` is set, say by another
+    /// thread, and returning the best move of the deepest search that finished
+    pub fn search_with_stop(&self, depth: u32, stop: &AtomicBool) -> Option<MoveInfo> {
+        let mut searcher = Searcher::new(&SearchOptions::default());
+        searcher.stop = Some(stop);
+
+        self.deepen(&mut searcher, depth)
+    }
+
+    /// Iterative deepening up to `max_depth`, until the searcher is stopped
+    fn deepen(&self, searcher: &mut Searcher, max_depth: u32) -> Option<MoveInfo> {
+        let mut position = self.clone();
+
+        // Fall back to any legal move if not even the first iteration completes
+        let mut best_move = self.ordered_moves().first().copied();
+
+        for depth in 1..=max_depth {
+            let (iteration_move, score) = searcher.root(&mut position, depth, best_move);
+            if searcher.stopped || iteration_move.is_none() {
+                break;
+            }
+
+            best_move = iteration_move;
+
+            // Nothing deeper can improve on a forced mate
+            if score.abs() > MATE_THRESHOLD {
+                break;
+            }
+        }
+
+        best_move
+    }
+
+    /// Alpha-beta score of the position from the side to play's perspective
+    pub fn search(&self, depth: u32, alpha: i32, beta: i32) -> i32 {
+        let options = SearchOptions { table_size: 0 };
+        Searcher::new(&options).alpha_beta(&mut self.clone(), depth, 0, alpha, beta)
+    }
+
+    /// Sorts captures to the front, most valuable victim first and then least valuable
+    /// attacker, leaving the other moves in their original order
+    pub fn order_captures(&self, moves: &mut [MoveInfo]) {
+        moves.sort_by_key(|piece_move| match piece_move.captures() {
+            Some(victim) => {
+                let attacker = piece_move.from().1.piece_kind().unwrap();
+                -(piece_value(victim) * 10 - piece_value(attacker))
+            }
+            None => 0,
+        });
+    }
+
+    /// Legal moves with captures first, as ordered by `order_captures`
+    fn ordered_moves(&self) -> Vec<MoveInfo> {
+        let mut moves = self.legal_moves();
+        self.order_captures(&mut moves);
+
+        moves
+    }
+
+    fn relative_evaluation(&self) -> i32 {
+        match self.to_play() {
+            Color::White => self.evaluate(),
+            Color::Black => -self.evaluate(),
+        }
+    }
+}
+
+struct Searcher<'a> {
+    table: TranspositionTable,
+    nodes: u64,
+    deadline: Option<Instant>,
+    stop: Option<&'a AtomicBool>,
+    stopped: bool,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(options: &SearchOptions) -> Self {
+        Self {
+            table: TranspositionTable::new(options.table_size),
+            nodes: 0,
+            deadline: None,
+            stop: None,
+            stopped: false,
+        }
+    }
+
+    /// Best move and score at the root, searching `first` before the other moves
+    fn root(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        first: Option<MoveInfo>,
+    ) -> (Option<MoveInfo>, i32) {
+        let mut best_move = None;
+        let mut alpha = -MATE_SCORE - 1;
+        let beta = MATE_SCORE + 1;
+
+        if position.state() != State::Playing {
+            return (None, self.alpha_beta(position, 0, 0, alpha, beta));
+        }
+
+        let mut moves = position.ordered_moves();
+        if let Some(i) = moves
+            .iter()
+            .position(|&piece_move| Some(piece_move) == first)
+        {
+            moves[..=i].rotate_right(1);
+        }
+
+        for piece_move in moves {
+            let token = position.make_move(&piece_move);
+            let score = -self.alpha_beta(position, depth.saturating_sub(1), 1, -beta, -alpha);
+            position.unmake_move(token);
+
+            if self.stopped {
+                break;
+            }
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(piece_move);
+            }
+        }
+
+        (best_move, alpha)
+    }
+
+    fn alpha_beta(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.visit() {
+            return 0;
+        }
+
+        if let State::Checkmate(_) = position.state() {
+            // Prefer quicker mates, and delay being mated as long as possible
+            return -(MATE_SCORE - ply);
+        }
+
+        if position.state() != State::Playing {
+            return position.relative_evaluation();
+        }
+        if depth == 0 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        let key = position.zobrist();
+        let entry = self.table.probe(key);
+
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return beta,
+                Bound::Upper if score <= alpha => return alpha,
+                _ => {}
+            }
+        }
+
+        let mut moves = position.ordered_moves();
+
+        // Try the best move from an earlier visit first
+        if let Some(best) = entry.and_then(|entry| entry.best_move) {
+            if let Some(i) = moves.iter().position(|&piece_move| piece_move == best) {
+                moves[..=i].rotate_right(1);
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+
+        for piece_move in moves {
+            let token = position.make_move(&piece_move);
+            let score = -self.alpha_beta(position, depth - 1, ply + 1, -beta, -alpha);
+            position.unmake_move(token);
+
+            // Scores from an interrupted search are meaningless, so leave the table alone
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                self.store(key, depth, beta, Bound::Lower, Some(piece_move), ply);
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(piece_move);
+            }
+        }
+
+        let bound = if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(key, depth, alpha, bound, best_move, ply);
+
+        alpha
+    }
+
+    /// Counts a node, returning whether the search has run out of time or been stopped
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+
+        // Only look at the clock and stop flag every so often, as they are comparatively slow
+        if self.nodes.is_multiple_of(1024)
+            && (self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+                || self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)))
+        {
+            self.stopped = true;
+        }
+
+        self.stopped
+    }
+
+    /// Keeps resolving captures past the search horizon so leaves are only scored once quiet
+    fn quiescence(&mut self, position: &mut Position, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.visit() {
+            return 0;
+        }
+
+        if let State::Checkmate(_) = position.state() {
+            return -(MATE_SCORE - ply);
+        }
+        if position.state() != State::Playing {
+            return position.relative_evaluation();
+        }
+
+        // Standing pat assumes some quiet move is at least as good as doing nothing,
+        // which doesn't hold when every move has to get out of check
+        let in_check = position.is_in_check();
+        if !in_check {
+            let stand_pat = position.relative_evaluation();
+            if stand_pat >= beta {
+                return beta;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let moves = position.ordered_moves().into_iter().filter(|piece_move| {
+            in_check || piece_move.captures().is_some() || piece_move.promotion().is_some()
+        });
+
+        for piece_move in moves {
+            let token = position.make_move(&piece_move);
+            let score = -self.quiescence(position, ply + 1, -beta, -alpha);
+            position.unmake_move(token);
+
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<MoveInfo>,
+        ply: i32,
+    ) {
+        self.table.store(Entry {
+            key,
+            depth,
+            score: score_to_table(score, ply),
+            bound,
+            best_move,
+        });
+    }
+}
+
+/// Mate scores are stored relative to the node so they stay valid at any ply
+fn score_to_table(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score + ply
+    } else if score < -MATE_THRESHOLD {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: i32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score - ply
+    } else if score < -MATE_THRESHOLD {
+        score + ply
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Piece;
+
+    #[test]
+    fn takes_a_free_queen() {
+        let position = Position::from_fen("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+
+        let best = position.best_move(2).unwrap();
+        assert_eq!(best.captures(), Some(Piece::Queen));
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let mut position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+
+        let best = position.best_move(2).unwrap();
+        position.make_move(&best);
+        assert_eq!(position.state(), State::Checkmate(Color::Black));
+    }
+
+    #[test]
+    fn no_move_once_the_game_is_over() {
+        let position = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(position.best_move(2), None);
+    }
+
+    /// Full-width negamax with the same leaf scoring as `alpha_beta`, for reference
+    fn minimax(searcher: &mut Searcher, position: &mut Position, depth: u32, ply: i32) -> i32 {
+        searcher.visit();
+
+        if let State::Checkmate(_) = position.state() {
+            return -(MATE_SCORE - ply);
+        }
+        if position.state() != State::Playing {
+            return position.relative_evaluation();
+        }
+        if depth == 0 {
+            return searcher.quiescence(position, ply, -MATE_SCORE - 1, MATE_SCORE + 1);
+        }
+
+        let mut best = -MATE_SCORE - 1;
+        for piece_move in position.ordered_moves() {
+            let token = position.make_move(&piece_move);
+            best = best.max(-minimax(searcher, position, depth - 1, ply + 1));
+            position.unmake_move(token);
+        }
+
+        best
+    }
+
+    fn minimax_root(position: &Position, depth: u32) -> (Option<MoveInfo>, i32, u64) {
+        let mut searcher = Searcher::new(&SearchOptions { table_size: 0 });
+        let mut position = position.clone();
+
+        let mut best = (None, -MATE_SCORE - 1);
+        for piece_move in position.ordered_moves() {
+            let token = position.make_move(&piece_move);
+            let score = -minimax(&mut searcher, &mut position, depth - 1, 1);
+            position.unmake_move(token);
+
+            if best.0.is_none() || score > best.1 {
+                best = (Some(piece_move), score);
+            }
+        }
+
+        (best.0, best.1, searcher.nodes)
+    }
+
+    #[test]
+    fn alpha_beta_agrees_with_minimax() {
+        let tactics = [
+            // Knight fork of king and queen
+            "4k3/8/2q5/8/8/3N4/8/4K3 w - - 0 1",
+            // Back rank mate
+            "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+            // Defended pawn that isn't worth the queen
+            "4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1",
+            // Promotion race
+            "8/P6k/8/8/8/8/6Kp/8 w - - 0 1",
+        ];
+
+        for fen in tactics {
+            let position = Position::from_fen(fen).unwrap();
+            let (minimax_move, minimax_score, minimax_nodes) = minimax_root(&position, 2);
+
+            let report = position.search_with(2, &SearchOptions { table_size: 0 });
+            assert_eq!(report.best_move, minimax_move, "{fen}");
+            assert_eq!(report.score, minimax_score, "{fen}");
+            assert_eq!(
+                position.search(2, -MATE_SCORE - 1, MATE_SCORE + 1),
+                minimax_score,
+                "{fen}"
+            );
+
+            assert!(
+                report.nodes < minimax_nodes,
+                "{fen}: {} alpha-beta nodes against {minimax_nodes} minimax nodes",
+                report.nodes
+            );
+        }
+    }
+
+    #[test]
+    fn table_saves_nodes_without_changing_the_move() {
+        // King moves transpose into each other all the time
+        let position = Position::from_fen("8/8/3k4/8/8/3K4/3P4/8 w - - 0 1").unwrap();
+
+        let without = position.search_with(5, &SearchOptions { table_size: 0 });
+        let with = position.search_with(5, &SearchOptions::default());
+
+        assert_eq!(with.best_move, without.best_move);
+        assert_eq!(with.score, without.score);
+        assert!(
+            with.nodes < without.nodes,
+            "{} nodes with the table against {} without",
+            with.nodes,
+            without.nodes
+        );
+    }
+
+    fn mates_in_one(position: &Position) -> bool {
+        position.legal_moves().iter().any(|piece_move| {
+            let mut after = position.clone();
+            after.make_move(piece_move);
+            matches!(after.state(), State::Checkmate(_))
+        })
+    }
+
+    #[test]
+    fn timed_search_finds_mate_in_two() {
+        let position = Position::from_fen("6k1/8/5K2/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert!(!mates_in_one(&position));
+
+        let mut position = position;
+        position.make_move(&position.best_move_timed(10_000).unwrap());
+
+        // Every defence still allows mate next move
+        let replies = position.legal_moves();
+        assert!(!replies.is_empty());
+        for reply in replies {
+            let mut after = position.clone();
+            after.make_move(&reply);
+            assert!(mates_in_one(&after), "{} escapes", reply.to_uci());
+        }
+    }
+
+    #[test]
+    fn timed_search_always_has_a_move() {
+        let position = Position::default();
+        let best = position.best_move_timed(0).unwrap();
+
+        assert!(position.legal_moves().contains(&best));
+    }
+
+    #[test]
+    fn quiescence_sees_the_recapture() {
+        let position = Position::from_fen("4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let queen_takes = position
+            .legal_moves()
+            .into_iter()
+            .find(|piece_move| piece_move.to_uci() == "d2d5")
+            .unwrap();
+
+        // Scoring the move by the position straight after it grabs the pawn
+        let naive = position
+            .legal_moves()
+            .into_iter()
+            .max_by_key(|piece_move| {
+                let mut after = position.clone();
+                after.make_move(piece_move);
+                after.evaluate()
+            })
+            .unwrap();
+        assert_eq!(naive, queen_takes);
+
+        assert_ne!(position.best_move(1), Some(queen_takes));
+        assert_ne!(position.best_move(2), Some(queen_takes));
+    }
+
+    #[test]
+    fn stop_flag_ends_the_search_promptly() {
+        let position = Position::standard();
+        let stop = AtomicBool::new(false);
+        let start = Instant::now();
+
+        let best = std::thread::scope(|scope| {
+            // Far deeper than could finish, so only the flag ends it
+            let search = scope.spawn(|| position.search_with_stop(64, &stop));
+            stop.store(true, Ordering::Relaxed);
+            search.join().unwrap()
+        });
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(position.legal_moves().contains(&best.unwrap()));
+    }
+
+    #[test]
+    fn stop_flag_set_beforehand_still_gives_a_move() {
+        let position = Position::from_fen("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+
+        let best = position.search_with_stop(64, &AtomicBool::new(true));
+
+        assert!(position.legal_moves().contains(&best.unwrap()));
+    }
+
+    #[test]
+    fn pawn_takes_queen_before_queen_takes_pawn() {
+        // Both the pawn on c4 and the queen on a5 can capture, the pawn taking the
+        // queen on d5 and the queen taking the pawn on a7
+        let position = Position::from_fen("4k3/p7/8/Q2q4/2P5/8/8/4K3 w - - 0 1").unwrap();
+        let mut moves = position.legal_moves();
+        moves.reverse();
+
+        position.order_captures(&mut moves);
+
+        let pxq = moves
+            .iter()
+            .position(|piece_move| piece_move.captures() == Some(Piece::Queen))
+            .unwrap();
+        let qxp = moves
+            .iter()
+            .position(|piece_move| {
+                piece_move.captures() == Some(Piece::Pawn)
+                    && piece_move.from().1.piece_kind() == Some(Piece::Queen)
+            })
+            .unwrap();
+        assert!(pxq < qxp);
+        assert_eq!(moves[0].from().1.piece_kind(), Some(Piece::Pawn));
+
+        // Quiet moves all come after the captures
+        let captures = moves
+            .iter()
+            .take_while(|piece_move| piece_move.captures().is_some());
+        assert_eq!(
+            captures.count(),
+            moves
+                .iter()
+                .filter(|piece_move| piece_move.captures().is_some())
+                .count()
+        );
+    }
+}
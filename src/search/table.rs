@@ -0,0 +1,53 @@
+use crate::position::MoveInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this high, after a beta cutoff
+    Lower,
+    /// The score is at most this high, as no move raised alpha
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<MoveInfo>,
+}
+
+/// Fixed-size table of search results keyed by Zobrist hash, always replacing on collision
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size],
+        }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.slot(key)
+            .and_then(|i| self.entries[i])
+            .filter(|entry| entry.key == key)
+    }
+
+    pub fn store(&mut self, entry: Entry) {
+        if let Some(i) = self.slot(entry.key) {
+            self.entries[i] = Some(entry);
+        }
+    }
+
+    fn slot(&self, key: u64) -> Option<usize> {
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some((key % self.entries.len() as u64) as usize)
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{self, BufRead, Write};
+
+use crate::position::Position;
+
+/// Depth searched for a bare `go` with no limits
+const DEFAULT_DEPTH: u32 = 4;
+
+/// Engine side of the UCI protocol, answering one GUI command at a time
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UciEngine {
+    position: Position,
+}
+
+impl UciEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Lines to send back for a single command, ignoring anything unrecognised as the
+    /// protocol asks
+    pub fn handle(&mut self, line: &str) -> Vec<String> {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("uci") => vec![
+                format!("id name {}", env!("CARGO_PKG_NAME")),
+                "uciok".to_string(),
+            ],
+            Some("isready") => vec!["readyok".to_string()],
+            Some("ucinewgame") => {
+                self.position = Position::standard();
+                vec![]
+            }
+            Some("position") => {
+                if let Some(position) = parse_position(tokens) {
+                    self.position = position;
+                }
+                vec![]
+            }
+            Some("go") => vec![self.go(tokens)],
+            _ => vec![],
+        }
+    }
+
+    fn go<'a>(&self, mut tokens: impl Iterator<Item = &'a str>) -> String {
+        let mut depth = None;
+        let mut movetime = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => depth = tokens.next().and_then(|value| value.parse().ok()),
+                "movetime" => movetime = tokens.next().and_then(|value| value.parse().ok()),
+                _ => {}
+            }
+        }
+
+        let best_move = match (depth, movetime) {
+            (None, Some(millis)) => self.position.best_move_timed(millis),
+            (depth, _) => self.position.best_move(depth.unwrap_or(DEFAULT_DEPTH)),
+        };
+
+        // `0000` is the protocol's null move, for positions that are already over
+        match best_move {
+            Some(best_move) => format!("bestmove {}", best_move.to_uci()),
+            None => "bestmove 0000".to_string(),
+        }
+    }
+}
+
+/// `startpos` or `fen <fields>`, optionally followed by `moves` and a list of UCI moves.
+/// The moves are played up to the first illegal one
+fn parse_position<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<Position> {
+    let tokens: Vec<&str> = tokens.collect();
+    let moves_at = tokens
+        .iter()
+        .position(|&token| token == "moves")
+        .unwrap_or(tokens.len());
+    let (setup, moves) = tokens.split_at(moves_at);
+
+    let mut position = match setup {
+        ["startpos"] => Position::standard(),
+        ["fen", fields @ ..] => Position::from_fen(&fields.join(" ")).ok()?,
+        _ => return None,
+    };
+
+    // A bad move only cuts the list short, as the protocol has no way to report it
+    if let [_, moves @ ..] = moves {
+        let _ = position.apply_uci_line(&moves.join(" "));
+    }
+
+    Some(position)
+}
+
+/// Answers commands from `input` on `output` until `quit` or the end of the input
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut engine = UciEngine::new();
+
+    for line in input.lines() {
+        let line = line?;
+        if line.trim() == "quit" {
+            break;
+        }
+
+        for response in engine.handle(&line) {
+            writeln!(output, "{response}")?;
+        }
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_session() {
+        let script = "uci\n\
+                      isready\n\
+                      position startpos moves e2e4 e7e5\n\
+                      go depth 1\n\
+                      position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\n\
+                      go depth 2\n\
+                      position fen R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1\n\
+                      go movetime 10\n\
+                      quit\n\
+                      go depth 1\n";
+        let mut output = Vec::new();
+        run(script.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Nothing after `quit` is answered
+        assert_eq!(lines.len(), 6, "{output}");
+        assert_eq!(lines[0], "id name chess");
+        assert_eq!(lines[1], "uciok");
+        assert_eq!(lines[2], "readyok");
+        assert!(lines[3].starts_with("bestmove "));
+        assert_eq!(lines[4], "bestmove a1a8");
+        assert_eq!(lines[5], "bestmove 0000");
+    }
+
+    #[test]
+    fn position_commands_apply_moves() {
+        let mut engine = UciEngine::new();
+
+        assert!(engine
+            .handle("position startpos moves e2e4 e7e5 g1f3")
+            .is_empty());
+        assert_eq!(
+            engine.position().to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        let reply = engine.handle("go depth 1");
+        assert_eq!(reply.len(), 1);
+        let uci = reply[0].strip_prefix("bestmove ").unwrap();
+        assert!(engine.position().parse_uci_move(uci).is_ok(), "{uci}");
+
+        // Unknown commands and bad setups are ignored
+        assert!(engine.handle("position nonsense").is_empty());
+        assert!(engine.handle("xyzzy").is_empty());
+        assert_eq!(engine.position().fullmove_number(), 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! JavaScript bindings. Build the module with
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+//! and run `wasm-bindgen` on the output.
+
+use wasm_bindgen::prelude::*;
+
+use crate::game::Game;
+use crate::position::{DrawReason, State};
+
+/// Thin JavaScript-facing wrapper around `Game`, speaking UCI moves and FEN
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(js_name = legalMovesUci)]
+    pub fn legal_moves_uci(&self) -> Vec<String> {
+        self.game
+            .position()
+            .legal_moves()
+            .iter()
+            .map(|piece_move| piece_move.to_uci())
+            .collect()
+    }
+
+    /// Plays a move such as `e2e4` or `e7e8q`, returning whether it was legal
+    #[wasm_bindgen(js_name = playUci)]
+    pub fn play_uci(&mut self, uci: &str) -> bool {
+        let Ok(piece_move) = self.game.position().parse_uci_move(uci) else {
+            return false;
+        };
+
+        self.game
+            .play(
+                piece_move.from().0,
+                piece_move.to().0,
+                piece_move.promotion(),
+            )
+            .is_ok()
+    }
+
+    pub fn fen(&self) -> String {
+        self.game.position().to_fen()
+    }
+
+    #[wasm_bindgen(js_name = stateString)]
+    pub fn state_string(&self) -> String {
+        let state = match self.game.state() {
+            State::Playing => "playing",
+            State::Checkmate(_) => "checkmate",
+            State::Stalemate(_) => "stalemate",
+            State::Draw(DrawReason::FiftyMove) => "draw by fifty-move rule",
+            State::Draw(DrawReason::ThreefoldRepetition) => "draw by threefold repetition",
+            State::Draw(DrawReason::InsufficientMaterial) => "draw by insufficient material",
+        };
+
+        state.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_a_game_through_uci() {
+        let mut game = WasmGame::new();
+        assert_eq!(game.legal_moves_uci().len(), 20);
+        assert!(game.legal_moves_uci().contains(&"g1f3".to_string()));
+
+        for uci in ["f2f3", "e7e5", "g2g4"] {
+            assert!(game.play_uci(uci), "{uci}");
+        }
+        assert!(!game.play_uci("e8e6"));
+        assert!(!game.play_uci("not a move"));
+        assert_eq!(game.state_string(), "playing");
+
+        assert!(game.play_uci("d8h4"));
+        assert_eq!(game.state_string(), "checkmate");
+        assert_eq!(
+            game.fen(),
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+        );
+        assert!(game.legal_moves_uci().is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use chess::position::Position;
+
+/// The six standard perft positions, one per line followed by `;D<depth> <nodes>` counts.
+/// Depths are cut short of the published tables to keep unoptimised runs quick, while
+/// still reaching castling, en passant and promotions in every line that has them
+const SUITE: &str = include_str!("perft.epd");
+
+#[test]
+fn perft_suite() {
+    for line in SUITE.lines().filter(|line| !line.trim().is_empty()) {
+        let mut fields = line.split(';');
+        let fen = fields.next().unwrap().trim();
+        let position = Position::from_fen(fen).unwrap();
+
+        for field in fields {
+            let (depth, expected) = field.trim()[1..].split_once(' ').unwrap();
+            let depth: u32 = depth.parse().unwrap();
+            let expected: u64 = expected.parse().unwrap();
+
+            assert_eq!(position.perft(depth), expected, "perft({depth}) of {fen}");
+        }
+    }
+}